@@ -0,0 +1,41 @@
+//! Measure the OSC dispatch throughput for a title-heavy corpus.
+//!
+//! Run with `cargo run --release --example osc_bench`.
+use std::time::Instant;
+
+use vte::{Parser, Perform};
+
+/// Number of times the corpus is parsed.
+const ITERATIONS: usize = 200;
+
+/// A type implementing Perform that only counts OSC parameters.
+struct Counter(usize);
+
+impl Perform for Counter {
+    fn osc_dispatch(&mut self, params: &[&[u8]], _bell_terminated: bool) {
+        self.0 += params.len();
+    }
+}
+
+fn main() {
+    // Shells commonly set the title for every prompt and command.
+    let mut corpus = Vec::new();
+    for i in 0..10_000 {
+        corpus.extend_from_slice(format!("\x1b]0;user@host: ~/src/{i}\x07$ ls\r\n").as_bytes());
+        corpus.extend_from_slice(format!("\x1b]2;ls ~/src/{i}\x1b\\").as_bytes());
+        corpus.extend_from_slice(b"\x1b]133;A\x07");
+    }
+
+    let mut parser = Parser::new();
+    let mut counter = Counter(0);
+
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        parser.advance(&mut counter, &corpus);
+    }
+    let elapsed = start.elapsed();
+
+    let bytes = corpus.len() * ITERATIONS;
+    let throughput = bytes as f64 / elapsed.as_secs_f64() / 1024. / 1024.;
+    println!("{} params in {:?} ({:.1} MiB/s)", counter.0, elapsed, throughput);
+}
@@ -1,7 +1,7 @@
 //! Parse input from stdin and log actions on stdout
-use std::io::{self, Read};
+use std::io;
 
-use vte::{Params, Parser, Perform};
+use vte::{Params, Perform, ReadParser};
 
 /// A type implementing Perform that just logs actions
 struct Log;
@@ -51,21 +51,10 @@ impl Perform for Log {
 
 fn main() {
     let input = io::stdin();
-    let mut handle = input.lock();
-
-    let mut statemachine = Parser::new();
+    let mut statemachine = ReadParser::new(input.lock());
     let mut performer = Log;
 
-    let mut buf = [0; 2048];
-
-    loop {
-        match handle.read(&mut buf) {
-            Ok(0) => break,
-            Ok(n) => statemachine.advance(&mut performer, &buf[..n]),
-            Err(err) => {
-                println!("err: {}", err);
-                break;
-            },
-        }
+    if let Err(err) = statemachine.run(&mut performer) {
+        println!("err: {}", err);
     }
 }
@@ -6,8 +6,6 @@
 
 //! ANSI Terminal Stream Parsing.
 
-extern crate alloc;
-
 use alloc::borrow::ToOwned;
 use alloc::string::{String, ToString};
 use alloc::vec::Vec;
@@ -20,32 +18,22 @@ use core::str::FromStr;
 use core::time::Duration;
 use core::{iter, mem, str};
 #[cfg(feature = "std")]
+use std::io::IoSlice;
+#[cfg(feature = "std")]
 use std::time::Instant;
 
 use bitflags::bitflags;
 #[doc(inline)]
 pub use cursor_icon;
 use cursor_icon::CursorIcon;
+#[cfg(all(not(feature = "std"), feature = "libm"))]
+use libm::pow;
 use log::debug;
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
-use crate::{Params, ParamsIter};
-
-/// Maximum time before a synchronized update is aborted.
-const SYNC_UPDATE_TIMEOUT: Duration = Duration::from_millis(150);
-
-/// Maximum number of bytes read in one synchronized update (2MiB).
-const SYNC_BUFFER_SIZE: usize = 0x20_0000;
-
-/// Number of bytes in the BSU/ESU CSI sequences.
-const SYNC_ESCAPE_LEN: usize = 8;
-
-/// BSU CSI sequence for beginning or extending synchronized updates.
-const BSU_CSI: [u8; SYNC_ESCAPE_LEN] = *b"\x1b[?2026h";
-
-/// ESU CSI sequence for terminating synchronized updates.
-const ESU_CSI: [u8; SYNC_ESCAPE_LEN] = *b"\x1b[?2026l";
+use crate::sync::{BSU_CSI, ESU_CSI, SYNC_BUFFER_SIZE, SYNC_ESCAPE_LEN, SYNC_UPDATE_TIMEOUT};
+use crate::{osc, OscParamsExt, Params, ParamsIter};
 
 #[derive(Debug, PartialEq, Eq, Hash)]
 pub struct Hyperlink {
@@ -67,14 +55,14 @@ impl Rgb {
     /// Implementation of [W3C's luminance algorithm].
     ///
     /// [W3C's luminance algorithm]: https://www.w3.org/TR/WCAG20/#relativeluminancedef
-    #[cfg(feature = "std")]
+    #[cfg(any(feature = "std", feature = "libm"))]
     pub fn luminance(self) -> f64 {
         let channel_luminance = |channel| {
             let channel = channel as f64 / 255.;
             if channel <= 0.03928 {
                 channel / 12.92
             } else {
-                f64::powf((channel + 0.055) / 1.055, 2.4)
+                pow((channel + 0.055) / 1.055, 2.4)
             }
         };
 
@@ -88,7 +76,7 @@ impl Rgb {
     /// Implementation of [W3C's contrast algorithm].
     ///
     /// [W3C's contrast algorithm]: https://www.w3.org/TR/WCAG20/#contrast-ratiodef
-    #[cfg(feature = "std")]
+    #[cfg(any(feature = "std", feature = "libm"))]
     pub fn contrast(self, other: Rgb) -> f64 {
         let self_luminance = self.luminance();
         let other_luminance = other.luminance();
@@ -101,6 +89,36 @@ impl Rgb {
 
         (lighter + 0.05) / (darker + 0.05)
     }
+
+    /// Linearly interpolate between `self` and `other`.
+    ///
+    /// An `alpha` of `0.0` returns `self`, while `1.0` returns `other`; values
+    /// outside of that range are clamped. Blending is done in sRGB space,
+    /// without linearizing the channels first.
+    pub fn blend(self, other: Rgb, alpha: f32) -> Rgb {
+        let alpha = alpha.clamp(0., 1.);
+        let channel = |from: u8, to: u8| {
+            let value = f32::from(from) + (f32::from(to) - f32::from(from)) * alpha;
+            (value + 0.5).clamp(0., 255.) as u8
+        };
+
+        Rgb {
+            r: channel(self.r, other.r),
+            g: channel(self.g, other.g),
+            b: channel(self.b, other.b),
+        }
+    }
+
+    /// Average of `self` and `other`, in sRGB space.
+    pub fn mix(self, other: Rgb) -> Rgb {
+        self.blend(other, 0.5)
+    }
+}
+
+/// Raise `base` to the power of `exp`, this uses `libm::pow` without `std`.
+#[cfg(feature = "std")]
+fn pow(base: f64, exp: f64) -> f64 {
+    base.powf(exp)
 }
 
 // A multiply function for Rgb, as the default dim is just *2/3.
@@ -226,27 +244,180 @@ fn parse_legacy_color(color: &[u8]) -> Option<Rgb> {
     })
 }
 
-fn parse_number(input: &[u8]) -> Option<u8> {
-    if input.is_empty() {
-        return None;
-    }
-    let mut num: u8 = 0;
-    for c in input {
-        let c = *c as char;
-        let digit = c.to_digit(10)?;
-        num = num.checked_mul(10).and_then(|v| v.checked_add(digit as u8))?;
+/// Parse the arguments of an iTerm2 proprietary escape sequence.
+///
+/// See [`Handler::iterm2_proprietary`] for the format of the keys.
+#[allow(clippy::type_complexity)]
+fn parse_iterm2_proprietary<'a>(
+    params: &[&'a [u8]],
+) -> Option<(Vec<(&'a str, &'a str)>, Option<&'a [u8]>)> {
+    let key_value = |arg: &'a [u8]| -> Option<(&'a str, &'a str)> {
+        let arg = str::from_utf8(arg).ok()?;
+        Some(arg.split_once('=').unwrap_or((arg, "")))
+    };
+
+    let file_args = match params[0].strip_prefix(b"File=") {
+        Some(args) => args,
+        None => {
+            let keys = params.iter().map(|&arg| key_value(arg)).collect::<Option<_>>()?;
+            return Some((keys, None));
+        },
+    };
+
+    // The base64 payload follows the last file argument.
+    let mut args: Vec<&[u8]> = iter::once(file_args).chain(params[1..].iter().copied()).collect();
+    let last = args.pop()?;
+    let (last, payload) = match last.iter().position(|&byte| byte == b':') {
+        Some(index) => (&last[..index], Some(&last[index + 1..])),
+        None => (last, None),
+    };
+    args.push(last);
+
+    let mut keys = alloc::vec![("File", "")];
+    for arg in args.into_iter().filter(|arg| !arg.is_empty()) {
+        keys.push(key_value(arg)?);
     }
-    Some(num)
+
+    Some((keys, payload))
+}
+
+/// Parse a decimal number, rejecting values which do not fit into `T`.
+fn parse_number<T: TryFrom<u32>>(input: &[u8]) -> Option<T> {
+    osc::parse_number(input).and_then(|num| T::try_from(num).ok())
 }
 
+/// Maximum number of bytes buffered for a single DCS.
+///
+/// Buffered DCSs exceeding this limit are dropped.
+const DCS_BUFFER_SIZE: usize = 0x10_0000;
+
 /// Internal state for VTE processor.
 #[derive(Debug, Default)]
 struct ProcessorState<T: Timeout> {
     /// Last processed character for repetition.
     preceding_char: Option<char>,
 
+    /// Payload of the active DCS, if it is buffered for dispatch on unhook.
+    dcs_buffer: Option<(BufferedDcs, Vec<u8>)>,
+
+    /// State of the active tmux passthrough payload.
+    tmux_passthrough: Option<TmuxPassthroughState>,
+
+    /// State of the active DECDLD soft font definition.
+    soft_font: Option<SoftFontState>,
+
     /// State for synchronized terminal updates.
     sync_state: SyncState<T>,
+
+    /// State of the VT52 compatibility mode.
+    vt52: Vt52State,
+
+    /// Function for calculating the width of printed characters.
+    #[cfg(feature = "wide-chars")]
+    char_width: Option<fn(char) -> usize>,
+
+    /// Private modes tracked for answering DECRQM, if tracking is enabled.
+    tracked_modes: Option<TrackedModes>,
+
+    /// Whether whitespace surrounding window titles is kept.
+    preserve_title_whitespace: bool,
+
+    /// Whether a dispatched sequence changed the screen since the last
+    /// [`Processor::advance_tracked`].
+    visible_change: bool,
+}
+
+impl<T: Timeout> ProcessorState<T> {
+    /// Append a byte to the buffered DCS.
+    #[inline]
+    fn push_dcs_byte(&mut self, byte: u8) {
+        match &mut self.dcs_buffer {
+            Some((_, buffer)) if buffer.len() < DCS_BUFFER_SIZE => buffer.push(byte),
+            Some((kind, _)) => {
+                debug!("[unhandled dcs] {:?} exceeded {} bytes", kind, DCS_BUFFER_SIZE);
+                self.dcs_buffer = None;
+            },
+            None => debug!("[unhandled put] byte={:?}", byte),
+        }
+    }
+}
+
+/// Kind of a DCS which is buffered until unhook.
+#[derive(Debug, Copy, Clone)]
+enum BufferedDcs {
+    /// Potential tmux passthrough, until its `tmux;` prefix is complete.
+    Tmux,
+    /// Unescaped payload of a tmux passthrough.
+    TmuxPassthrough,
+    /// DECRSPS presentation state.
+    PresentationState(PresentationStateKind),
+    /// DECRSTS terminal state.
+    TerminalState(TerminalStateKind),
+}
+
+/// State of a tmux passthrough payload.
+///
+/// The payload doubles the escapes of the wrapped sequences, which would
+/// terminate the DCS in the parser. So the [`Processor`] unescapes the payload
+/// itself, only passing the final escape to the parser.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum TmuxPassthroughState {
+    /// Inside of the payload.
+    Payload,
+    /// After an escape, which is either doubled or terminates the DCS.
+    Escape,
+}
+
+/// State of a DECDLD soft font definition.
+#[derive(Debug)]
+enum SoftFontState {
+    /// Header parameters were parsed, waiting for the `Dscs` designator.
+    Designator(SoftFontHeader),
+    /// Header was dispatched, streaming the glyph data.
+    Data,
+}
+
+/// Private modes tracked by the processor.
+#[derive(Debug, Default, Copy, Clone)]
+struct TrackedModes {
+    report_focus_in_out: bool,
+    bracketed_paste: bool,
+    sync_update: bool,
+}
+
+impl TrackedModes {
+    /// Get the state of a private mode, if it is tracked.
+    fn get_mut(&mut self, mode: u16) -> Option<&mut bool> {
+        match mode {
+            1004 => Some(&mut self.report_focus_in_out),
+            2004 => Some(&mut self.bracketed_paste),
+            2026 => Some(&mut self.sync_update),
+            _ => None,
+        }
+    }
+}
+
+/// State of the VT52 compatibility mode.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+enum Vt52State {
+    /// VT52 mode cannot be entered.
+    #[default]
+    Unsupported,
+    /// ANSI mode, VT52 mode can be entered by resetting DECANM.
+    Ansi,
+    /// VT52 mode.
+    Vt52,
+    /// VT52 direct cursor address, waiting for the line.
+    AddressLine,
+    /// VT52 direct cursor address, waiting for the column.
+    AddressColumn(usize),
+}
+
+impl Vt52State {
+    /// Check if escapes should be interpreted using the VT52 grammar.
+    fn is_vt52(self) -> bool {
+        matches!(self, Self::Vt52 | Self::AddressLine | Self::AddressColumn(_))
+    }
 }
 
 #[derive(Debug)]
@@ -264,6 +435,16 @@ impl<T: Timeout> Default for SyncState<T> {
     }
 }
 
+/// Result of [`Processor::advance_tracked`].
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct AdvanceOutcome {
+    /// Whether a dispatched sequence changed the screen content.
+    pub visible_change: bool,
+    /// Whether a synchronized update is pending, so repainting should be
+    /// deferred until it ends.
+    pub sync_pending: bool,
+}
+
 /// The processor wraps a `crate::Parser` to ultimately call methods on a
 /// Handler.
 #[cfg(feature = "std")]
@@ -275,6 +456,9 @@ pub struct Processor<T: Timeout = StdSyncHandler> {
 
 /// The processor wraps a `crate::Parser` to ultimately call methods on a
 /// Handler.
+///
+/// Without the `std` feature, a [`Timeout`] implementation for synchronized
+/// updates must be provided.
 #[cfg(not(feature = "std"))]
 #[derive(Default)]
 pub struct Processor<T: Timeout> {
@@ -293,6 +477,45 @@ impl<T: Timeout> Processor<T> {
         &self.state.sync_state.timeout
     }
 
+    /// Allow entering VT52 compatibility mode by resetting DECANM (`CSI ? 2
+    /// l`).
+    ///
+    /// In VT52 mode, escapes are interpreted using the VT52 grammar until ANSI
+    /// mode is restored by `ESC <`. Disabling support while in VT52 mode
+    /// immediately returns to ANSI mode.
+    pub fn set_vt52_support(&mut self, supported: bool) {
+        self.state.vt52 = if supported { Vt52State::Ansi } else { Vt52State::Unsupported };
+    }
+
+    /// Track the state of some private modes, to answer DECRQM for them.
+    ///
+    /// When enabled, the processor answers `CSI ? Ps $ p` for focus reporting
+    /// (1004), bracketed paste (2004) and synchronized updates (2026) through
+    /// [`Handler::report_private_mode_state`]. All tracked modes start out
+    /// reset and are reset again by RIS (`ESC c`).
+    pub fn set_track_private_modes(&mut self, track: bool) {
+        self.state.tracked_modes = if track { Some(TrackedModes::default()) } else { None };
+    }
+
+    /// Remove leading and trailing whitespace from window titles.
+    ///
+    /// This is enabled by default. When disabled, titles are passed to
+    /// [`Handler::set_title`] exactly as received, including titles consisting
+    /// only of whitespace.
+    pub fn set_trim_titles(&mut self, trim: bool) {
+        self.state.preserve_title_whitespace = !trim;
+    }
+
+    /// Set the function used to calculate the width of printed characters.
+    ///
+    /// Once set, printed characters are passed to [`Handler::input_with_width`]
+    /// instead of [`Handler::input`]. This is usually a thin wrapper around a
+    /// crate like `unicode-width`.
+    #[cfg(feature = "wide-chars")]
+    pub fn set_char_width(&mut self, char_width: fn(char) -> usize) {
+        self.state.char_width = Some(char_width);
+    }
+
     /// Process a new byte from the PTY.
     #[inline]
     pub fn advance<H>(&mut self, handler: &mut H, bytes: &[u8])
@@ -304,18 +527,123 @@ impl<T: Timeout> Processor<T> {
             if self.state.sync_state.timeout.pending_timeout() {
                 processed += self.advance_sync(handler, &bytes[processed..]);
             } else {
-                let mut performer = Performer::new(&mut self.state, handler);
-                processed +=
-                    self.parser.advance_until_terminated(&mut performer, &bytes[processed..]);
+                processed += self.advance_parser(handler, &bytes[processed..]);
+            }
+        }
+    }
+
+    /// Process new bytes, without buffering synchronized updates.
+    ///
+    /// Returns the number of bytes processed, which is less than `bytes` when
+    /// the parser was terminated.
+    #[inline]
+    fn advance_parser<H>(&mut self, handler: &mut H, bytes: &[u8]) -> usize
+    where
+        H: Handler,
+    {
+        if self.state.tmux_passthrough.is_some() {
+            return self.advance_tmux_passthrough(handler, bytes);
+        }
+
+        let mut performer = Performer::new(&mut self.state, handler);
+        self.parser.advance_until_terminated(&mut performer, bytes)
+    }
+
+    /// Process the payload of a tmux passthrough.
+    ///
+    /// Returns the number of bytes processed, stopping at the end of the
+    /// payload.
+    #[cold]
+    fn advance_tmux_passthrough<H>(&mut self, handler: &mut H, bytes: &[u8]) -> usize
+    where
+        H: Handler,
+    {
+        for (i, &byte) in bytes.iter().enumerate() {
+            let escape = self.state.tmux_passthrough == Some(TmuxPassthroughState::Escape);
+            match byte {
+                C0::ESC if escape => {
+                    self.state.push_dcs_byte(C0::ESC);
+                    self.state.tmux_passthrough = Some(TmuxPassthroughState::Payload);
+                },
+                // Other escapes terminate the DCS, the parser handles the rest of the sequence.
+                _ if escape => {
+                    self.state.tmux_passthrough = None;
+                    let mut performer = Performer::new(&mut self.state, handler);
+                    self.parser.advance(&mut performer, &[C0::ESC]);
+                    return i;
+                },
+                C0::ESC => self.state.tmux_passthrough = Some(TmuxPassthroughState::Escape),
+                // Leave cancellation and the 8-bit ST to the parser.
+                C0::CAN | C0::SUB | C1::ST => {
+                    self.state.tmux_passthrough = None;
+                    return i;
+                },
+                _ => self.state.push_dcs_byte(byte),
             }
         }
+
+        bytes.len()
+    }
+
+    /// Process new bytes from multiple buffers as one continuous stream.
+    ///
+    /// Sequences can be split across buffers, which avoids copying the
+    /// buffers of a vectored read into a single slice before processing them.
+    #[cfg(feature = "std")]
+    pub fn advance_vectored<H>(&mut self, handler: &mut H, bufs: &[IoSlice<'_>])
+    where
+        H: Handler,
+    {
+        for buf in bufs {
+            self.advance(handler, buf);
+        }
+    }
+
+    /// Process new bytes from the PTY, reporting if a repaint is necessary.
+    ///
+    /// This is equivalent to [`Self::advance`], but additionally tracks
+    /// whether any of the dispatched sequences printed text, moved the cursor,
+    /// erased or scrolled content or changed attributes, modes or colors.
+    pub fn advance_tracked<H>(&mut self, handler: &mut H, bytes: &[u8]) -> AdvanceOutcome
+    where
+        H: Handler,
+    {
+        self.state.visible_change = false;
+        self.advance(handler, bytes);
+
+        AdvanceOutcome {
+            visible_change: self.state.visible_change,
+            sync_pending: self.state.sync_state.timeout.pending_timeout(),
+        }
+    }
+
+    /// Signal the end of the input stream.
+    ///
+    /// This processes the bytes of a pending synchronized update, then
+    /// flushes a codepoint that was cut off by the end of the input, passing
+    /// the replacement character to [`Handler::input`].
+    pub fn end<H>(&mut self, handler: &mut H)
+    where
+        H: Handler,
+    {
+        if self.state.sync_state.timeout.pending_timeout() {
+            handler.sync_update_aborted(SyncAbortReason::Explicit);
+            self.stop_sync_internal(handler, None);
+        }
+
+        let mut performer = Performer::new(&mut self.state, handler);
+        self.parser.end(&mut performer);
     }
 
     /// End a synchronized update.
+    ///
+    /// This should be called once the synchronized update timeout has
+    /// expired, so it is reported as [`SyncAbortReason::Timeout`].
     pub fn stop_sync<H>(&mut self, handler: &mut H)
     where
         H: Handler,
     {
+        handler.sync_update_aborted(SyncAbortReason::Timeout);
         self.stop_sync_internal(handler, None);
     }
 
@@ -334,8 +662,10 @@ impl<T: Timeout> Processor<T> {
         // processed automatically during the synchronized update.
         let buffer = mem::take(&mut self.state.sync_state.buffer);
         let offset = bsu_offset.unwrap_or(buffer.len());
-        let mut performer = Performer::new(&mut self.state, handler);
-        self.parser.advance(&mut performer, &buffer[..offset]);
+        let mut processed = 0;
+        while processed != offset {
+            processed += self.advance_parser(handler, &buffer[processed..offset]);
+        }
         self.state.sync_state.buffer = buffer;
 
         match bsu_offset {
@@ -350,6 +680,9 @@ impl<T: Timeout> Processor<T> {
             },
             // Report mode and clear state if no new BSU is present.
             None => {
+                if let Some(tracked_modes) = &mut self.state.tracked_modes {
+                    tracked_modes.sync_update = false;
+                }
                 handler.unset_private_mode(NamedPrivateMode::SyncUpdate.into());
                 self.state.sync_state.timeout.clear_timeout();
                 self.state.sync_state.buffer.clear();
@@ -363,6 +696,47 @@ impl<T: Timeout> Processor<T> {
         self.state.sync_state.buffer.len()
     }
 
+    /// Remove the bytes buffered by the active synchronized update.
+    ///
+    /// This ends the synchronized update and clears its timeout, without
+    /// dispatching any of the buffered bytes. Like any other end of a
+    /// synchronized update, this resets [`NamedPrivateMode::SyncUpdate`]. The
+    /// bytes can be moved to another processor using [`Self::set_sync_buffer`].
+    pub fn take_sync_buffer<H>(&mut self, handler: &mut H) -> Vec<u8>
+    where
+        H: Handler,
+    {
+        if self.state.sync_state.timeout.pending_timeout() {
+            if let Some(tracked_modes) = &mut self.state.tracked_modes {
+                tracked_modes.sync_update = false;
+            }
+            handler.unset_private_mode(NamedPrivateMode::SyncUpdate.into());
+            self.state.sync_state.timeout.clear_timeout();
+        }
+
+        mem::take(&mut self.state.sync_state.buffer)
+    }
+
+    /// Start a synchronized update with `bytes` already buffered.
+    ///
+    /// This replaces any bytes buffered by an active synchronized update and
+    /// resets its timeout. If no synchronized update was active,
+    /// [`NamedPrivateMode::SyncUpdate`] is set like for a BSU.
+    pub fn set_sync_buffer<H>(&mut self, handler: &mut H, bytes: Vec<u8>)
+    where
+        H: Handler,
+    {
+        if !self.state.sync_state.timeout.pending_timeout() {
+            if let Some(tracked_modes) = &mut self.state.tracked_modes {
+                tracked_modes.sync_update = true;
+            }
+            handler.set_private_mode(NamedPrivateMode::SyncUpdate.into());
+        }
+
+        self.state.sync_state.timeout.set_timeout(SYNC_UPDATE_TIMEOUT);
+        self.state.sync_state.buffer = bytes;
+    }
+
     /// Process a new byte during a synchronized update.
     ///
     /// Returns the number of bytes processed.
@@ -374,11 +748,11 @@ impl<T: Timeout> Processor<T> {
         // Advance sync parser or stop sync if we'd exceed the maximum buffer size.
         if self.state.sync_state.buffer.len() + bytes.len() >= SYNC_BUFFER_SIZE - 1 {
             // Terminate the synchronized update.
+            handler.sync_update_aborted(SyncAbortReason::BufferFull);
             self.stop_sync_internal(handler, None);
 
             // Just parse the bytes normally.
-            let mut performer = Performer::new(&mut self.state, handler);
-            self.parser.advance_until_terminated(&mut performer, bytes)
+            self.advance_parser(handler, bytes)
         } else {
             self.state.sync_state.buffer.extend(bytes);
             self.advance_sync_csi(handler, bytes.len());
@@ -488,14 +862,35 @@ pub trait Timeout: Default {
     fn pending_timeout(&self) -> bool;
 }
 
+/// Reason for ending a synchronized update without its terminating escape.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SyncAbortReason {
+    /// The synchronized update exceeded the maximum buffer size.
+    BufferFull,
+    /// The synchronized update timed out, through [`Processor::stop_sync`].
+    Timeout,
+    /// The input ended during the synchronized update, through
+    /// [`Processor::end`].
+    Explicit,
+}
+
 /// Type that handles actions from the parser.
 ///
-/// XXX Should probably not provide default impls for everything, but it makes
-/// writing specific handler impls for tests far easier.
+/// All methods have default implementations, which also allows adding new
+/// methods without breaking existing implementations.
 pub trait Handler {
     /// OSC to set window title.
+    ///
+    /// Leading and trailing whitespace is removed from the title, unless
+    /// disabled using `Processor::set_trim_titles`.
     fn set_title(&mut self, _: Option<String>) {}
 
+    /// XTSMTITLE - Enable the given title modes (`CSI > Ps ; Ps t`).
+    fn set_title_modes(&mut self, _modes: TitleModes) {}
+
+    /// XTRMTITLE - Disable the given title modes (`CSI > Ps ; Ps T`).
+    fn reset_title_modes(&mut self, _modes: TitleModes) {}
+
     /// Set the cursor style.
     fn set_cursor_style(&mut self, _: Option<CursorStyle>) {}
 
@@ -505,6 +900,29 @@ pub trait Handler {
     /// A character to be displayed.
     fn input(&mut self, _c: char) {}
 
+    /// A character to be displayed, along with its width in cells.
+    ///
+    /// This is only called when a width function was set using
+    /// `Processor::set_char_width`, which requires the `wide-chars` feature.
+    /// By default this calls [`Self::input`].
+    fn input_with_width(&mut self, c: char, _width: usize) {
+        self.input(c);
+    }
+
+    /// REP - Repeat the preceding graphic character `count` times.
+    ///
+    /// The character is only repeated if REP directly follows it, any other
+    /// control function in between it and REP causes REP to be ignored. The
+    /// `count` refers to characters, not cells, so wide characters are also
+    /// repeated `count` times.
+    ///
+    /// By default this calls [`Self::input`] once for every repetition.
+    fn repeat_preceding(&mut self, count: usize, c: char) {
+        for _ in 0..count {
+            self.input(c);
+        }
+    }
+
     /// Set cursor to position.
     fn goto(&mut self, _line: i32, _col: usize) {}
 
@@ -526,15 +944,49 @@ pub trait Handler {
     /// Identify the terminal (should write back to the pty stream).
     fn identify_terminal(&mut self, _intermediate: Option<char>) {}
 
+    /// Identify the terminal in VT52 mode (should write `ESC / Z` back to the
+    /// pty stream).
+    fn identify_terminal_vt52(&mut self) {}
+
     /// Report device status.
     fn device_status(&mut self, _: usize) {}
 
+    /// XTVERSION - Report the terminal's name and version.
+    ///
+    /// The reply can be created using [`format_xtversion`].
+    fn report_terminal_version(&mut self) {}
+
+    /// Report DEC private device status (`CSI ? Ps n`).
+    ///
+    /// This includes queries like DECXCPR (6), printer status (15), UDK status
+    /// (25) and keyboard language (26).
+    fn device_status_private(&mut self, _: usize) {}
+
     /// Move cursor forward `cols`.
     fn move_forward(&mut self, _col: usize) {}
 
     /// Move cursor backward `cols`.
     fn move_backward(&mut self, _col: usize) {}
 
+    /// Move cursor relative to its current position.
+    ///
+    /// This is called for CUU, CUD, CUF and CUB, with a negative `dy` moving
+    /// up and a negative `dx` moving backward. Only one of the deltas is
+    /// non-zero for each sequence.
+    fn move_cursor(&mut self, dx: i32, dy: i32) {
+        if dy < 0 {
+            self.move_up(dy.unsigned_abs() as usize);
+        } else if dy > 0 {
+            self.move_down(dy as usize);
+        }
+
+        if dx < 0 {
+            self.move_backward(dx.unsigned_abs() as usize);
+        } else if dx > 0 {
+            self.move_forward(dx as usize);
+        }
+    }
+
     /// Move cursor down `rows` and set to column 1.
     fn move_down_and_cr(&mut self, _row: usize) {}
 
@@ -558,6 +1010,18 @@ pub trait Handler {
     /// Hopefully this is never implemented.
     fn bell(&mut self) {}
 
+    /// DECSWBV - Set the warning bell volume.
+    ///
+    /// The `level` ranges from `1` (off) to `8` (loudest), with `0` requesting
+    /// the terminal's default volume.
+    fn set_warning_bell_volume(&mut self, _level: u8) {}
+
+    /// DECSMBV - Set the margin bell volume.
+    ///
+    /// The `level` ranges from `1` (off) to `8` (loudest), with `0` requesting
+    /// the terminal's default volume.
+    fn set_margin_bell_volume(&mut self, _level: u8) {}
+
     /// Substitute char under cursor.
     fn substitute(&mut self) {}
 
@@ -571,6 +1035,9 @@ pub trait Handler {
     fn scroll_up(&mut self, _: usize) {}
 
     /// Scroll down `rows` rows.
+    ///
+    /// This is dispatched for both SD (`CSI Ps T`) and its ECMA-48 alias
+    /// (`CSI Ps ^`).
     fn scroll_down(&mut self, _: usize) {}
 
     /// Insert `count` blank lines.
@@ -609,6 +1076,14 @@ pub trait Handler {
     /// Clear screen.
     fn clear_screen(&mut self, _mode: ClearMode) {}
 
+    /// Clear the scrollback buffer (`CSI 3 J`).
+    ///
+    /// By default this calls [`Handler::clear_screen`] with
+    /// [`ClearMode::Saved`].
+    fn clear_scrollback(&mut self) {
+        self.clear_screen(ClearMode::Saved);
+    }
+
     /// Clear tab stops.
     fn clear_tabs(&mut self, _mode: TabulationClearMode) {}
 
@@ -643,18 +1118,72 @@ pub trait Handler {
     /// Unset private mode.
     fn unset_private_mode(&mut self, _mode: PrivateMode) {}
 
+    /// Synchronized update ended without its terminating escape.
+    ///
+    /// This is called before the buffered bytes are processed.
+    fn sync_update_aborted(&mut self, _reason: SyncAbortReason) {}
+
+    /// Private modes without a [`NamedPrivateMode`], reported once per DECSET
+    /// or DECRST after the individual modes have been set or unset.
+    fn unknown_private_modes(&mut self, _modes: &[u16]) {}
+
     /// DECRPM - report private mode.
     fn report_private_mode(&mut self, _mode: PrivateMode) {}
 
+    /// DECRPM - report private mode, with its state tracked by the
+    /// [`Processor`].
+    ///
+    /// This is only called for modes tracked after enabling
+    /// `Processor::set_track_private_modes`. By default this calls
+    /// [`Self::report_private_mode`], the reply can be formatted using
+    /// [`format_decrpm_private`].
+    fn report_private_mode_state(&mut self, mode: PrivateMode, _state: ModeState) {
+        self.report_private_mode(mode);
+    }
+
+    /// XTSAVE - Save the values of private modes.
+    fn save_private_modes(&mut self, _modes: &[PrivateMode]) {}
+
+    /// XTRESTORE - Restore the previously saved values of private modes.
+    fn restore_private_modes(&mut self, _modes: &[PrivateMode]) {}
+
     /// DECSTBM - Set the terminal scrolling region.
+    ///
+    /// Both margins are 1-based. A `bottom` of `None` refers to the last line
+    /// of the screen, an explicit `bottom` is always greater than `top`. Like
+    /// xterm, regions with `top >= bottom` are ignored and passed to
+    /// [`Handler::unhandled_csi`] instead. Resetting the margins is dispatched
+    /// to [`Handler::reset_scrolling_region`] instead.
     fn set_scrolling_region(&mut self, _top: usize, _bottom: Option<usize>) {}
 
+    /// DECSTBM - Reset the scrolling region to the full screen.
+    ///
+    /// This is called for `CSI r` without parameters, or with parameters
+    /// selecting the entire screen.
+    fn reset_scrolling_region(&mut self) {
+        self.set_scrolling_region(1, None);
+    }
+
     /// DECKPAM - Set keypad to applications mode (ESCape instead of digits).
     fn set_keypad_application_mode(&mut self) {}
 
     /// DECKPNM - Set keypad to numeric mode (digits instead of ESCape seq).
     fn unset_keypad_application_mode(&mut self) {}
 
+    /// S7C1T/S8C1T - Select whether replies use 7-bit or 8-bit C1 controls.
+    ///
+    /// Replies can be converted to 8-bit controls using
+    /// [`encode_c1_controls`].
+    fn set_c1_transmission(&mut self, _eight_bit: bool) {}
+
+    /// DECSCL - Set the conformance level.
+    ///
+    /// The `level` is the terminal's VT level, like `1` for VT100 and `2` for
+    /// VT200 compatibility, while `eight_bit` selects 8-bit controls for
+    /// replies. This is also called with 7-bit controls for the legacy ANSI
+    /// conformance levels, `ESC SP L`, `ESC SP M` and `ESC SP N`.
+    fn set_conformance_level(&mut self, _level: u8, _eight_bit: bool) {}
+
     /// Set one of the graphic character sets, G0 to G3, as the active charset.
     ///
     /// 'Invoke' one of G0 to G3 in the GL area. Also referred to as shift in,
@@ -677,32 +1206,81 @@ pub trait Handler {
     fn reset_color(&mut self, _: usize) {}
 
     /// Store data into clipboard.
+    ///
+    /// This is only called by the default implementation of
+    /// [`Self::clipboard_store_selectors`], with the first selector.
     fn clipboard_store(&mut self, _: u8, _: &[u8]) {}
 
     /// Load data from clipboard.
+    ///
+    /// This is only called by the default implementation of
+    /// [`Self::clipboard_load_selectors`], with the first selector.
     fn clipboard_load(&mut self, _: u8, _: &str) {}
 
-    /// Run the decaln routine.
+    /// Store data into all clipboards of the OSC 52 `selectors`.
+    ///
+    /// Each selector is one of `c`, `p`, `q`, `s` or `0` to `7`. An empty
+    /// selection defaults to `c`. By default this calls
+    /// [`Self::clipboard_store`] with the first selector.
+    fn clipboard_store_selectors(&mut self, selectors: &[u8], data: &[u8]) {
+        self.clipboard_store(selectors.first().copied().unwrap_or(b'c'), data);
+    }
+
+    /// Load data from the clipboards of the OSC 52 `selectors`.
+    ///
+    /// Each selector is one of `c`, `p`, `q`, `s` or `0` to `7`. An empty
+    /// selection defaults to `c`. By default this calls
+    /// [`Self::clipboard_load`] with the first selector.
+    fn clipboard_load_selectors(&mut self, selectors: &[u8], terminator: &str) {
+        self.clipboard_load(selectors.first().copied().unwrap_or(b'c'), terminator);
+    }
+
+    /// DECALN - Screen alignment pattern (`ESC # 8`).
+    ///
+    /// This should reset the scrolling region to the full screen, move the
+    /// cursor to the top-left corner and fill the whole screen with `E`,
+    /// using the default attributes.
     fn decaln(&mut self) {}
 
-    /// Push a title onto the stack.
-    fn push_title(&mut self) {}
+    /// Push the titles selected by `target` onto the stack.
+    fn push_title(&mut self, _target: TitleTarget) {}
 
-    /// Pop the last title from the stack.
-    fn pop_title(&mut self) {}
+    /// Pop the titles selected by `target` from the stack.
+    fn pop_title(&mut self, _target: TitleTarget) {}
 
     /// Report text area size in pixels.
+    ///
+    /// The reply can be created using [`format_text_area_pixels`].
     fn text_area_size_pixels(&mut self) {}
 
     /// Report text area size in characters.
+    ///
+    /// The reply can be created using [`format_text_area_chars`].
     fn text_area_size_chars(&mut self) {}
 
     /// Set hyperlink.
     fn set_hyperlink(&mut self, _: Option<Hyperlink>) {}
 
+    /// iTerm2 proprietary escape sequence (`OSC 1337 ; Ps ST`).
+    ///
+    /// Every `;` separated argument is split into a key and value at its first
+    /// `=`, so `SetMark` is passed as `[("SetMark", "")]` and `CurrentDir=/tmp`
+    /// as `[("CurrentDir", "/tmp")]`.
+    ///
+    /// File transfers and inline images (`File=args:base64`) are passed as a
+    /// `("File", "")` key followed by their arguments, with the base64 data
+    /// after the `:` as `payload`.
+    fn iterm2_proprietary(&mut self, _keys: &[(&str, &str)], _payload: Option<&[u8]>) {}
+
     /// Set mouse cursor icon.
     fn set_mouse_cursor_icon(&mut self, _: CursorIcon) {}
 
+    /// Set a mouse cursor icon which is not a known [`CursorIcon`].
+    ///
+    /// This receives the raw OSC 22 shape name, allowing support for custom
+    /// or themed cursors.
+    fn set_mouse_cursor_icon_raw(&mut self, _name: &str) {}
+
     /// Report current keyboard mode.
     fn report_keyboard_mode(&mut self) {}
 
@@ -713,6 +1291,17 @@ pub trait Handler {
     /// keyboard mode stack.
     fn pop_keyboard_modes(&mut self, _to_pop: u16) {}
 
+    /// XTPUSHSGR - Push the current SGR attributes onto the stack.
+    ///
+    /// This handles both `CSI Pm # {` and its alias `CSI Pm # p`. Without
+    /// explicit `items`, all attributes should be pushed.
+    fn push_sgr(&mut self, _items: Option<SgrStackItems>) {}
+
+    /// XTPOPSGR - Restore the SGR attributes pushed last.
+    ///
+    /// This handles both `CSI # }` and its alias `CSI # q`.
+    fn pop_sgr(&mut self) {}
+
     /// Set the [`keyboard mode`] using the given [`behavior`].
     ///
     /// [`keyboard mode`]: crate::ansi::KeyboardModes
@@ -729,41 +1318,490 @@ pub trait Handler {
 
     // Set SCP control.
     fn set_scp(&mut self, _char_path: ScpCharPath, _update_mode: ScpUpdateMode) {}
-}
 
-bitflags! {
-    /// A set of [`kitty keyboard protocol'] modes.
+    /// MC - Media copy, controlling printer output.
     ///
-    /// [`kitty keyboard protocol']: https://sw.kovidgoyal.net/kitty/keyboard-protocol
-    #[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Hash)]
-    pub struct KeyboardModes : u8 {
-        /// No keyboard protocol mode is set.
-        const NO_MODE                 = 0b0000_0000;
-        /// Report `Esc`, `alt` + `key`, `ctrl` + `key`, `ctrl` + `alt` + `key`, `shift`
-        /// + `alt` + `key` keys using `CSI u` sequence instead of raw ones.
-        const DISAMBIGUATE_ESC_CODES  = 0b0000_0001;
-        /// Report key presses, release, and repetition alongside the escape. Key events
-        /// that result in text are reported as plain UTF-8, unless the
-        /// [`Self::REPORT_ALL_KEYS_AS_ESC`] is enabled.
-        const REPORT_EVENT_TYPES      = 0b0000_0010;
-        /// Additionally report shifted key an dbase layout key.
-        const REPORT_ALTERNATE_KEYS   = 0b0000_0100;
-        /// Report every key as an escape sequence.
-        const REPORT_ALL_KEYS_AS_ESC  = 0b0000_1000;
-        /// Report the text generated by the key event.
-        const REPORT_ASSOCIATED_TEXT  = 0b0001_0000;
-    }
-}
+    /// The `private` flag is set for the DEC private form (`CSI ? Ps i`).
+    fn media_copy(&mut self, _mode: MediaCopyMode, _private: bool) {}
 
-/// XTMODKEYS modifyOtherKeys state.
-///
-/// This only applies to keys corresponding to ascii characters.
-///
-/// For the details on how to implement the mode handling correctly, consult
-/// [`XTerm's implementation`] and the [`output`] of XTerm's provided [`perl
-/// script`]. Some libraries and implementations also use the [`fixterms`]
-/// definition of the `CSI u`.
-///
+    /// DECSASD - Select whether output is sent to the main display or the
+    /// status line.
+    fn select_status_display(&mut self, _display: StatusDisplay) {}
+
+    /// DECSSDT - Select the type of status line.
+    fn set_status_display_type(&mut self, _kind: StatusDisplayType) {}
+
+    /// DECSACE - Select whether rectangular attribute changes apply to a
+    /// rectangle or to the stream of characters between its corners.
+    fn select_attribute_change_extent(&mut self, _extent: AttributeChangeExtent) {}
+
+    /// tmux passthrough (`DCS tmux ; data ST`).
+    ///
+    /// The `inner` data has its escapes unescaped already, so it can be fed
+    /// back into a [`Processor`] to handle the wrapped sequences.
+    fn tmux_passthrough(&mut self, _inner: &[u8]) {}
+
+    /// DECRSPS - Restore presentation state (`DCS Ps $ t data ST`).
+    ///
+    /// The `data` has the format of the corresponding DECCIR or DECTABSR
+    /// report.
+    fn restore_presentation_state(&mut self, _kind: PresentationStateKind, _data: &[u8]) {}
+
+    /// DECRSTS - Restore terminal state (`DCS Ps $ p data ST`).
+    ///
+    /// The `data` has the format of the corresponding DECTSR or DECCTR
+    /// report.
+    fn restore_terminal_state(&mut self, _kind: TerminalStateKind, _data: &[u8]) {}
+
+    /// DECDLD - Define a soft font (`DCS Pfn ; ... ; Pcss { Dscs data ST`).
+    ///
+    /// The sixel encoded glyph data following the header is passed to
+    /// [`Self::soft_font_put`], until [`Self::soft_font_end`] is called.
+    fn define_soft_font(&mut self, _header: SoftFontHeader) {}
+
+    /// Glyph data of the soft font started by [`Self::define_soft_font`].
+    fn soft_font_put(&mut self, _byte: u8) {}
+
+    /// End of the soft font started by [`Self::define_soft_font`].
+    ///
+    /// Fonts cancelled by CAN or SUB are `aborted` and should be discarded.
+    fn soft_font_end(&mut self, _aborted: bool) {}
+
+    /// CSI sequence which is not handled by the [`Processor`].
+    ///
+    /// This allows supporting sequences like VT520's `,`-intermediate controls
+    /// without having to parse the raw stream separately.
+    fn unhandled_csi(&mut self, _action: char, _params: &Params, _intermediates: &[u8]) {}
+
+    /// OSC sequence received by the [`Processor`], before it is interpreted.
+    ///
+    /// Returning `true` suppresses the built-in handling of the sequence,
+    /// including the call to [`Handler::unhandled_osc`].
+    fn osc_raw(&mut self, _params: &[&[u8]], _bell_terminated: bool) -> bool {
+        false
+    }
+
+    /// OSC sequence which is not handled by the [`Processor`].
+    fn unhandled_osc(&mut self, _params: &[&[u8]], _bell_terminated: bool) {}
+
+    /// ESC sequence which is not handled by the [`Processor`].
+    fn unhandled_esc(&mut self, _intermediates: &[u8], _byte: u8) {}
+
+    /// SGR parameter, including its subparameters, which could not be
+    /// converted to an [`Attr`].
+    fn unhandled_sgr(&mut self, _param: &[u16]) {}
+}
+
+/// Format the XTVERSION reply (`DCS > | name version ST`).
+pub fn format_xtversion(name: &str, version: &str) -> String {
+    alloc::format!("\x1bP>|{name} {version}\x1b\\")
+}
+
+/// Format the DECRPM reply for a private mode (`CSI ? Pd ; Ps $ y`).
+pub fn format_decrpm_private(mode: PrivateMode, state: ModeState) -> String {
+    alloc::format!("\x1b[?{};{}$y", mode.raw(), state as u8)
+}
+
+/// Format the OSC 52 reply for a clipboard query (`OSC 52 ; Pc ; Pd ST`).
+///
+/// The clipboard `data` is base64 encoded, while the `selector` and
+/// `terminator` should be the ones passed to [`Handler::clipboard_load`].
+pub fn format_clipboard_response(selector: u8, data: &[u8], terminator: &str) -> String {
+    let mut response = alloc::format!("\x1b]52;{};", selector as char);
+    encode_base64(&mut response, data);
+    response.push_str(terminator);
+    response
+}
+
+/// Append the padded base64 encoding of `data`.
+fn encode_base64(out: &mut String, data: &[u8]) {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+    out.reserve((data.len() + 2) / 3 * 4);
+    for chunk in data.chunks(3) {
+        let b0 = chunk[0] as usize;
+        let b1 = chunk.get(1).copied().unwrap_or(0) as usize;
+        let b2 = chunk.get(2).copied().unwrap_or(0) as usize;
+
+        out.push(ALPHABET[b0 >> 2] as char);
+        out.push(ALPHABET[((b0 & 0x03) << 4) | (b1 >> 4)] as char);
+        out.push(if chunk.len() > 1 {
+            ALPHABET[((b1 & 0x0F) << 2) | (b2 >> 6)] as char
+        } else {
+            '='
+        });
+        out.push(if chunk.len() > 2 { ALPHABET[b2 & 0x3F] as char } else { '=' });
+    }
+}
+
+/// Incremental base64 decoder.
+///
+/// This allows decoding large payloads like OSC 52 clipboard data while they
+/// arrive in chunks, without buffering the encoded data. Groups split across
+/// chunks are completed by the next chunk.
+///
+/// ```rust
+/// use vte::ansi::Base64Stream;
+///
+/// let mut decoder = Base64Stream::new();
+/// let mut decoded = Vec::new();
+///
+/// decoder.decode(b"dnR", &mut decoded).unwrap();
+/// decoder.decode(b"lIQ==", &mut decoded).unwrap();
+/// decoder.finish(&mut decoded).unwrap();
+///
+/// assert_eq!(decoded, b"vte!");
+/// ```
+#[derive(Debug, Default, Clone)]
+pub struct Base64Stream {
+    sextets: [u8; 4],
+    len: usize,
+    padded: bool,
+}
+
+impl Base64Stream {
+    /// Create a new decoder.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Decode a chunk of base64 data, appending the decoded bytes to `out`.
+    ///
+    /// Bytes of an incomplete group are kept until the next chunk.
+    pub fn decode(&mut self, chunk: &[u8], out: &mut Vec<u8>) -> Result<(), Base64Error> {
+        for &byte in chunk {
+            if byte == b'=' {
+                // Padding completes a group of at least two characters.
+                if !self.padded {
+                    if self.len < 2 {
+                        return Err(Base64Error);
+                    }
+                    self.flush(out)?;
+                    self.padded = true;
+                }
+                continue;
+            }
+
+            let sextet = match byte {
+                b'A'..=b'Z' => byte - b'A',
+                b'a'..=b'z' => byte - b'a' + 26,
+                b'0'..=b'9' => byte - b'0' + 52,
+                b'+' => 62,
+                b'/' => 63,
+                _ => return Err(Base64Error),
+            };
+
+            // No data is allowed after the padding.
+            if self.padded {
+                return Err(Base64Error);
+            }
+
+            self.sextets[self.len] = sextet;
+            self.len += 1;
+            if self.len == self.sextets.len() {
+                self.flush(out)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Decode the remaining bytes of an unpadded final group.
+    ///
+    /// This resets the decoder, so it can be used for the next payload.
+    pub fn finish(&mut self, out: &mut Vec<u8>) -> Result<(), Base64Error> {
+        let result = self.flush(out);
+        *self = Self::default();
+        result
+    }
+
+    /// Decode the current group.
+    fn flush(&mut self, out: &mut Vec<u8>) -> Result<(), Base64Error> {
+        let [a, b, c, d] = self.sextets;
+        match self.len {
+            0 => (),
+            1 => return Err(Base64Error),
+            len => {
+                out.push(a << 2 | b >> 4);
+                if len > 2 {
+                    out.push(b << 4 | c >> 2);
+                }
+                if len > 3 {
+                    out.push(c << 6 | d);
+                }
+            },
+        }
+
+        self.len = 0;
+        Ok(())
+    }
+}
+
+/// Error for invalid data passed to [`Base64Stream`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Base64Error;
+
+impl Display for Base64Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str("invalid base64 data")
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for Base64Error {}
+
+/// Format the text area size reply in pixels (`CSI 4 ; height ; width t`).
+pub fn format_text_area_pixels(height: u16, width: u16) -> String {
+    alloc::format!("\x1b[4;{height};{width}t")
+}
+
+/// Format the text area size reply in characters (`CSI 8 ; rows ; cols t`).
+pub fn format_text_area_chars(rows: u16, cols: u16) -> String {
+    alloc::format!("\x1b[8;{rows};{cols}t")
+}
+
+/// Format the DECRQSS reply for the current SGR attributes (`DCS 1 $ r Ps m
+/// ST`).
+///
+/// Attributes which cannot be represented as SGR parameters, like named
+/// colors other than the 16 ANSI colors, are omitted.
+pub fn format_decrqss_sgr(attrs: &[Attr]) -> String {
+    let mut setting = String::new();
+    for attr in attrs {
+        let len = setting.len();
+        if len != 0 {
+            setting.push(';');
+        }
+
+        if !push_sgr_params(&mut setting, attr) {
+            setting.truncate(len);
+        }
+    }
+
+    if setting.is_empty() {
+        setting.push('0');
+    }
+
+    setting.push('m');
+    format_decrqss(&setting)
+}
+
+/// Format the DECRQSS reply for the scrolling region (`DCS 1 $ r Pt ; Pb r
+/// ST`).
+pub fn format_decrqss_decstbm(top: usize, bottom: usize) -> String {
+    format_decrqss(&alloc::format!("{top};{bottom}r"))
+}
+
+/// Format the DECRQSS reply for the cursor style (`DCS 1 $ r Ps SP q ST`).
+///
+/// The style is encoded like in [`encode_cursor_style`].
+pub fn format_decrqss_decscusr(style: CursorStyle) -> String {
+    format_decrqss(&alloc::format!("{} q", decscusr_id(style)))
+}
+
+/// Wrap a setting in a valid DECRQSS reply.
+fn format_decrqss(setting: &str) -> String {
+    alloc::format!("\x1bP1$r{setting}\x1b\\")
+}
+
+/// DECRPSS reply to a DECRQSS status string request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DecrpssReport<'a> {
+    /// Whether the terminal recognized the requested setting.
+    pub valid: bool,
+    /// The setting in the form of its control sequence, without the CSI.
+    ///
+    /// For SGR this would be `0m`, for example. This is usually empty for
+    /// invalid requests.
+    pub setting: &'a [u8],
+}
+
+/// Parse a DECRPSS reply (`DCS Ps $ r D...D ST`).
+///
+/// Both the 7-bit and 8-bit forms of the DCS introducer and string terminator
+/// are accepted.
+///
+/// ```rust
+/// use vte::ansi::{parse_decrpss, DecrpssReport};
+///
+/// let report = parse_decrpss(b"\x1bP1$r0m\x1b\\");
+/// assert_eq!(report, Some(DecrpssReport { valid: true, setting: b"0m" }));
+/// ```
+pub fn parse_decrpss(data: &[u8]) -> Option<DecrpssReport<'_>> {
+    let data = data.strip_prefix(b"\x1bP").or_else(|| data.strip_prefix(&[C1::DCS]))?;
+    let data = data.strip_suffix(b"\x1b\\").or_else(|| data.strip_suffix(&[C1::ST]))?;
+
+    let valid = match data.get(..3)? {
+        b"1$r" => true,
+        b"0$r" => false,
+        _ => return None,
+    };
+
+    Some(DecrpssReport { valid, setting: &data[3..] })
+}
+
+/// Append the SGR parameters for an attribute.
+///
+/// Returns `false` if the attribute cannot be represented as SGR parameters.
+fn push_sgr_params(out: &mut String, attr: &Attr) -> bool {
+    let params = attr.to_sgr();
+    for (i, param) in params.iter().enumerate() {
+        for (j, value) in param.iter().enumerate() {
+            let separator = match (i, j) {
+                (0, 0) => "",
+                (_, 0) => ";",
+                _ => ":",
+            };
+            let _ = write!(out, "{separator}{value}");
+        }
+    }
+
+    !params.is_empty()
+}
+
+/// SGR parameters for a color.
+///
+/// The `base` is the first SGR parameter of the color's group, i.e. `30` for
+/// foreground, `40` for background and `50` for underline colors.
+fn sgr_color_params(color: Color, base: u16) -> Vec<Vec<u16>> {
+    // Only foreground and background colors have short forms for named colors.
+    let short_forms = base != 50;
+
+    let params: &[u16] = match color {
+        Color::Named(NamedColor::Foreground) if base == 30 => &[39],
+        Color::Named(NamedColor::Background) if base == 40 => &[49],
+        Color::Named(color) if (color as usize) < 8 && short_forms => {
+            return alloc::vec![alloc::vec![base + color as u16]];
+        },
+        Color::Named(color) if (color as usize) < 16 && short_forms => {
+            return alloc::vec![alloc::vec![base + 60 + color as u16 - 8]];
+        },
+        Color::Named(color) if (color as usize) < 16 => &[base + 8, 5, color as u16],
+        Color::Named(_) => &[],
+        Color::Indexed(index) => &[base + 8, 5, index as u16],
+        Color::Spec(Rgb { r, g, b }) => &[base + 8, 2, r as u16, g as u16, b as u16],
+    };
+
+    params.iter().map(|&param| alloc::vec![param]).collect()
+}
+
+bitflags! {
+    /// A set of [`kitty keyboard protocol'] modes.
+    ///
+    /// [`kitty keyboard protocol']: https://sw.kovidgoyal.net/kitty/keyboard-protocol
+    #[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub struct KeyboardModes : u8 {
+        /// No keyboard protocol mode is set.
+        const NO_MODE                 = 0b0000_0000;
+        /// Report `Esc`, `alt` + `key`, `ctrl` + `key`, `ctrl` + `alt` + `key`, `shift`
+        /// + `alt` + `key` keys using `CSI u` sequence instead of raw ones.
+        const DISAMBIGUATE_ESC_CODES  = 0b0000_0001;
+        /// Report key presses, release, and repetition alongside the escape. Key events
+        /// that result in text are reported as plain UTF-8, unless the
+        /// [`Self::REPORT_ALL_KEYS_AS_ESC`] is enabled.
+        const REPORT_EVENT_TYPES      = 0b0000_0010;
+        /// Additionally report shifted key an dbase layout key.
+        const REPORT_ALTERNATE_KEYS   = 0b0000_0100;
+        /// Report every key as an escape sequence.
+        const REPORT_ALL_KEYS_AS_ESC  = 0b0000_1000;
+        /// Report the text generated by the key event.
+        const REPORT_ASSOCIATED_TEXT  = 0b0001_0000;
+    }
+}
+
+bitflags! {
+    /// A set of SGR attributes saved by XTPUSHSGR.
+    ///
+    /// Attributes are selected by the parameter of the SGR setting them, so
+    /// `1` to `5` select bold, faint, italic, underline and blink, `7` to `9`
+    /// inverse, invisible and crossed-out, and `21` double underline. The
+    /// foreground and background colors are selected by `30` and `31`.
+    #[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub struct SgrStackItems : u16 {
+        /// Bold.
+        const BOLD              = 0b0000_0000_0001;
+        /// Faint.
+        const FAINT             = 0b0000_0000_0010;
+        /// Italic.
+        const ITALIC            = 0b0000_0000_0100;
+        /// Underline.
+        const UNDERLINE         = 0b0000_0000_1000;
+        /// Blink.
+        const BLINK             = 0b0000_0001_0000;
+        /// Inverse.
+        const INVERSE           = 0b0000_0010_0000;
+        /// Invisible.
+        const INVISIBLE         = 0b0000_0100_0000;
+        /// Crossed-out.
+        const STRIKEOUT         = 0b0000_1000_0000;
+        /// Double underline.
+        const DOUBLE_UNDERLINE  = 0b0001_0000_0000;
+        /// Foreground color.
+        const FOREGROUND        = 0b0010_0000_0000;
+        /// Background color.
+        const BACKGROUND        = 0b0100_0000_0000;
+    }
+}
+
+bitflags! {
+    /// A set of xterm title modes, selecting how window titles are encoded.
+    #[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub struct TitleModes : u8 {
+        /// Titles set through OSC 0, 1 and 2 are hex encoded.
+        const SET_HEX       = 0b0001;
+        /// Title query replies are hex encoded.
+        const QUERY_HEX     = 0b0010;
+        /// Titles set through OSC 0, 1 and 2 are UTF-8, rather than ISO-8859-1.
+        const SET_UTF8      = 0b0100;
+        /// Title query replies are UTF-8, rather than ISO-8859-1.
+        const QUERY_UTF8    = 0b1000;
+    }
+}
+
+impl TitleModes {
+    /// Get the mode selected by an XTSMTITLE or XTRMTITLE parameter.
+    fn from_param(param: u16) -> Option<Self> {
+        let mode = match param {
+            0 => Self::SET_HEX,
+            1 => Self::QUERY_HEX,
+            2 => Self::SET_UTF8,
+            3 => Self::QUERY_UTF8,
+            _ => return None,
+        };
+        Some(mode)
+    }
+}
+
+impl SgrStackItems {
+    /// Get the item selected by an XTPUSHSGR parameter.
+    fn from_param(param: u16) -> Option<Self> {
+        let item = match param {
+            1 => Self::BOLD,
+            2 => Self::FAINT,
+            3 => Self::ITALIC,
+            4 => Self::UNDERLINE,
+            5 => Self::BLINK,
+            7 => Self::INVERSE,
+            8 => Self::INVISIBLE,
+            9 => Self::STRIKEOUT,
+            21 => Self::DOUBLE_UNDERLINE,
+            30 => Self::FOREGROUND,
+            31 => Self::BACKGROUND,
+            _ => return None,
+        };
+        Some(item)
+    }
+}
+
+/// XTMODKEYS modifyOtherKeys state.
+///
+/// This only applies to keys corresponding to ascii characters.
+///
+/// For the details on how to implement the mode handling correctly, consult
+/// [`XTerm's implementation`] and the [`output`] of XTerm's provided [`perl
+/// script`]. Some libraries and implementations also use the [`fixterms`]
+/// definition of the `CSI u`.
+///
 /// The end escape sequence has a `CSI char; modifiers u` form while the
 /// original `CSI 27 ; modifier ; char ~`. The clients should prefer the `CSI
 /// u`, since it has more adoption.
@@ -823,6 +1861,16 @@ pub struct CursorStyle {
     pub blinking: bool,
 }
 
+impl CursorStyle {
+    /// DECSCUSR escape sequence setting this cursor style.
+    ///
+    /// See [`encode_cursor_style`] for the encoding of shapes DECSCUSR cannot
+    /// represent.
+    pub fn to_decscusr(&self) -> String {
+        encode_cursor_style(*self)
+    }
+}
+
 /// Terminal cursor shape.
 #[derive(Debug, Default, Eq, PartialEq, Copy, Clone, Hash)]
 pub enum CursorShape {
@@ -843,6 +1891,477 @@ pub enum CursorShape {
     Hidden,
 }
 
+impl TryFrom<u16> for CursorShape {
+    type Error = ();
+
+    /// Shape of a DECSCUSR cursor style id.
+    ///
+    /// The default style (`0`) has no shape and is rejected.
+    fn try_from(id: u16) -> Result<Self, ()> {
+        match id {
+            1 | 2 => Ok(CursorShape::Block),
+            3 | 4 => Ok(CursorShape::Underline),
+            5 | 6 => Ok(CursorShape::Beam),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Encode a cursor style as DECSCUSR (`CSI Ps SP q`) escape sequence.
+///
+/// DECSCUSR cannot represent [`CursorShape::HollowBlock`] and
+/// [`CursorShape::Hidden`]. A hollow block is encoded as a regular block, while
+/// a hidden cursor resets the style to the terminal's default (`CSI 0 SP q`),
+/// since cursor visibility is controlled through DECTCEM instead.
+pub fn encode_cursor_style(style: CursorStyle) -> String {
+    alloc::format!("\x1b[{} q", decscusr_id(style))
+}
+
+/// DECSCUSR parameter for a cursor style.
+fn decscusr_id(style: CursorStyle) -> u8 {
+    let id = match style.shape {
+        CursorShape::Block | CursorShape::HollowBlock => 1,
+        CursorShape::Underline => 3,
+        CursorShape::Beam => 5,
+        CursorShape::Hidden => return 0,
+    };
+
+    // Odd values are blinking, even values are steady.
+    if style.blinking {
+        id
+    } else {
+        id + 1
+    }
+}
+
+/// Encode a cursor shape as `OSC 50 ; CursorShape=Ps ST` escape sequence.
+///
+/// Like DECSCUSR, the OSC 50 form has no representation for
+/// [`CursorShape::HollowBlock`] and [`CursorShape::Hidden`], so both are
+/// encoded as a block cursor.
+pub fn encode_cursor_shape_osc50(shape: CursorShape) -> String {
+    let id = match shape {
+        CursorShape::Block | CursorShape::HollowBlock | CursorShape::Hidden => 0,
+        CursorShape::Beam => 1,
+        CursorShape::Underline => 2,
+    };
+
+    alloc::format!("\x1b]50;CursorShape={id}\x1b\\")
+}
+
+/// Convert the 7-bit C1 controls (`ESC Fe`) in a reply to their 8-bit [`C1`]
+/// form.
+///
+/// This allows using the reply formatters when 8-bit controls were requested
+/// through [`Handler::set_c1_transmission`].
+pub fn encode_c1_controls(reply: &str) -> Vec<u8> {
+    let mut encoded = Vec::with_capacity(reply.len());
+    let mut bytes = reply.bytes().peekable();
+    while let Some(byte) = bytes.next() {
+        match bytes.peek() {
+            Some(&next @ 0x40..=0x5F) if byte == C0::ESC => {
+                bytes.next();
+                encoded.push(next + 0x40);
+            },
+            _ => encoded.push(byte),
+        }
+    }
+    encoded
+}
+
+/// Classification of a CSI sequence by its final character and intermediates.
+///
+/// This only considers the sequence's syntax, so a classified sequence might
+/// still be ignored by the [`Processor`] due to its parameters.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum CsiCommand {
+    /// ICH - Insert blank characters.
+    InsertBlank,
+    /// CUU - Move the cursor up.
+    MoveUp,
+    /// CUD/VPR - Move the cursor down.
+    MoveDown,
+    /// REP - Repeat the preceding character.
+    Repeat,
+    /// CUF/HPR - Move the cursor forward.
+    MoveForward,
+    /// DA - Request the terminal's identity.
+    IdentifyTerminal,
+    /// CUB - Move the cursor backward.
+    MoveBackward,
+    /// VPA - Move the cursor to a line.
+    GotoLine,
+    /// CNL - Move the cursor down to the first column.
+    MoveDownAndCr,
+    /// CPL - Move the cursor up to the first column.
+    MoveUpAndCr,
+    /// CHA/HPA - Move the cursor to a column.
+    GotoCol,
+    /// DECST8C - Reset tab stops.
+    SetTabs,
+    /// TBC - Clear tab stops.
+    ClearTabs,
+    /// CUP/HVP - Move the cursor to a position.
+    Goto,
+    /// SM - Set ANSI modes.
+    SetMode,
+    /// DECSET - Set private modes.
+    SetPrivateMode,
+    /// CHT - Move the cursor forward by tab stops.
+    MoveForwardTabs,
+    /// ED - Erase in display.
+    ClearScreen,
+    /// EL - Erase in line.
+    ClearLine,
+    /// SCP - Select character path.
+    SetScp,
+    /// IL - Insert blank lines.
+    InsertBlankLines,
+    /// RM - Reset ANSI modes.
+    UnsetMode,
+    /// DECRST - Reset private modes.
+    UnsetPrivateMode,
+    /// DL - Delete lines.
+    DeleteLines,
+    /// SGR - Set graphic rendition.
+    TerminalAttribute,
+    /// XTMODKEYS - Set key modifier options.
+    SetModifyOtherKeys,
+    /// XTQMODKEYS - Query key modifier options.
+    ReportModifyOtherKeys,
+    /// DSR - Device status report.
+    DeviceStatus,
+    /// DEC private device status report.
+    DeviceStatusPrivate,
+    /// DCH - Delete characters.
+    DeleteChars,
+    /// DECRQM - Request ANSI mode.
+    ReportMode,
+    /// DECRQM - Request private mode.
+    ReportPrivateMode,
+    /// XTVERSION - Request the terminal's name and version.
+    ReportTerminalVersion,
+    /// DECSCUSR - Set the cursor style.
+    SetCursorStyle,
+    /// DECSTBM - Set the scrolling region.
+    SetScrollingRegion,
+    /// XTRESTORE - Restore private modes.
+    RestorePrivateModes,
+    /// SU - Scroll up.
+    ScrollUp,
+    /// SCOSC - Save the cursor position.
+    SaveCursorPosition,
+    /// XTSAVE - Save private modes.
+    SavePrivateModes,
+    /// SD - Scroll down.
+    ///
+    /// xterm overloads `CSI Ps ; Ps ; Ps ; Ps ; Ps T` for highlight mouse
+    /// tracking, which uses the same final byte but is not SD.
+    ScrollDown,
+    /// XTWINOPS - Window manipulation.
+    WindowOps,
+    /// DECSWBV - Set the warning bell volume.
+    SetWarningBellVolume,
+    /// DECSMBV - Set the margin bell volume.
+    SetMarginBellVolume,
+    /// Query the kitty keyboard protocol mode.
+    ReportKeyboardMode,
+    /// Set the kitty keyboard protocol mode.
+    SetKeyboardMode,
+    /// Push a kitty keyboard protocol mode.
+    PushKeyboardMode,
+    /// Pop kitty keyboard protocol modes.
+    PopKeyboardModes,
+    /// SCORC - Restore the cursor position.
+    RestoreCursorPosition,
+    /// MC - Media copy.
+    MediaCopy,
+    /// MC - DEC private media copy.
+    MediaCopyPrivate,
+    /// XTPUSHSGR - Push SGR attributes.
+    PushSgr,
+    /// XTPOPSGR - Pop SGR attributes.
+    PopSgr,
+    /// DECSASD - Select the active status display.
+    SelectStatusDisplay,
+    /// DECSSDT - Select the status display type.
+    SetStatusDisplayType,
+    /// DECSCL - Set the conformance level.
+    SetConformanceLevel,
+    /// DECSACE - Select the attribute change extent.
+    SelectAttributeChangeExtent,
+    /// XTSMTITLE - Set title modes.
+    SetTitleModes,
+    /// XTRMTITLE - Reset title modes.
+    ResetTitleModes,
+    /// ECH - Erase characters.
+    EraseChars,
+    /// CBT - Move the cursor backward by tab stops.
+    MoveBackwardTabs,
+    /// Sequence without built-in handling.
+    Unknown,
+}
+
+impl CsiCommand {
+    /// Classify a CSI sequence.
+    pub fn parse(action: char, intermediates: &[u8]) -> Self {
+        match (action, intermediates) {
+            ('@', []) => Self::InsertBlank,
+            ('A', []) => Self::MoveUp,
+            ('B', []) | ('e', []) => Self::MoveDown,
+            ('b', []) => Self::Repeat,
+            ('C', []) | ('a', []) => Self::MoveForward,
+            // Space intermediates are reserved for other sequences.
+            ('c', _) if !intermediates.contains(&b' ') => Self::IdentifyTerminal,
+            ('D', []) => Self::MoveBackward,
+            ('d', []) => Self::GotoLine,
+            ('E', []) => Self::MoveDownAndCr,
+            ('F', []) => Self::MoveUpAndCr,
+            ('G', []) | ('`', []) => Self::GotoCol,
+            ('W', [b'?']) => Self::SetTabs,
+            ('g', []) => Self::ClearTabs,
+            ('H', []) | ('f', []) => Self::Goto,
+            ('h', []) => Self::SetMode,
+            ('h', [b'?']) => Self::SetPrivateMode,
+            ('I', []) => Self::MoveForwardTabs,
+            ('i', []) => Self::MediaCopy,
+            ('i', [b'?']) => Self::MediaCopyPrivate,
+            ('J', []) => Self::ClearScreen,
+            ('K', []) => Self::ClearLine,
+            ('k', [b' ']) => Self::SetScp,
+            ('L', []) => Self::InsertBlankLines,
+            ('l', []) => Self::UnsetMode,
+            ('l', [b'?']) => Self::UnsetPrivateMode,
+            ('M', []) => Self::DeleteLines,
+            ('m', []) => Self::TerminalAttribute,
+            ('m', [b'>']) => Self::SetModifyOtherKeys,
+            ('m', [b'?']) => Self::ReportModifyOtherKeys,
+            ('n', []) => Self::DeviceStatus,
+            ('n', [b'?']) => Self::DeviceStatusPrivate,
+            ('P', []) => Self::DeleteChars,
+            ('p', [b'$']) => Self::ReportMode,
+            ('p', [b'?', b'$']) => Self::ReportPrivateMode,
+            ('p', [b'"']) => Self::SetConformanceLevel,
+            ('q', [b'>']) => Self::ReportTerminalVersion,
+            ('q', [b' ']) => Self::SetCursorStyle,
+            ('r', []) => Self::SetScrollingRegion,
+            ('r', [b'?']) => Self::RestorePrivateModes,
+            ('S', []) => Self::ScrollUp,
+            ('s', []) => Self::SaveCursorPosition,
+            ('s', [b'?']) => Self::SavePrivateModes,
+            ('T', []) | ('^', []) => Self::ScrollDown,
+            ('t', []) => Self::WindowOps,
+            ('t', [b'>']) => Self::SetTitleModes,
+            ('T', [b'>']) => Self::ResetTitleModes,
+            ('t', [b' ']) => Self::SetWarningBellVolume,
+            ('u', [b' ']) => Self::SetMarginBellVolume,
+            ('u', [b'?']) => Self::ReportKeyboardMode,
+            ('u', [b'=']) => Self::SetKeyboardMode,
+            ('u', [b'>']) => Self::PushKeyboardMode,
+            ('u', [b'<']) => Self::PopKeyboardModes,
+            ('u', []) => Self::RestoreCursorPosition,
+            ('X', []) => Self::EraseChars,
+            ('{', [b'#']) | ('p', [b'#']) => Self::PushSgr,
+            ('}', [b'#']) | ('q', [b'#']) => Self::PopSgr,
+            ('}', [b'$']) => Self::SelectStatusDisplay,
+            ('~', [b'$']) => Self::SetStatusDisplayType,
+            ('x', [b'*']) => Self::SelectAttributeChangeExtent,
+            ('Z', []) => Self::MoveBackwardTabs,
+            _ => Self::Unknown,
+        }
+    }
+
+    /// Check if the command can change the screen content.
+    fn changes_screen(self) -> bool {
+        matches!(
+            self,
+            Self::InsertBlank
+                | Self::MoveUp
+                | Self::MoveDown
+                | Self::Repeat
+                | Self::MoveForward
+                | Self::MoveBackward
+                | Self::GotoLine
+                | Self::MoveDownAndCr
+                | Self::MoveUpAndCr
+                | Self::GotoCol
+                | Self::Goto
+                | Self::SetMode
+                | Self::SetPrivateMode
+                | Self::MoveForwardTabs
+                | Self::ClearScreen
+                | Self::ClearLine
+                | Self::InsertBlankLines
+                | Self::UnsetMode
+                | Self::UnsetPrivateMode
+                | Self::DeleteLines
+                | Self::TerminalAttribute
+                | Self::DeleteChars
+                | Self::SetCursorStyle
+                | Self::SetScrollingRegion
+                | Self::RestorePrivateModes
+                | Self::ScrollUp
+                | Self::ScrollDown
+                | Self::RestoreCursorPosition
+                | Self::PopSgr
+                | Self::EraseChars
+                | Self::MoveBackwardTabs
+        )
+    }
+}
+
+/// Identifier of a sequence dispatched by the [`Processor`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum SequenceId {
+    /// CSI sequence, classified by [`CsiCommand::parse`].
+    Csi(CsiCommand),
+    /// OSC sequence with its first parameter.
+    Osc(u16),
+    /// Escape sequence with its intermediates and final byte.
+    Esc(&'static [u8], u8),
+    /// DCS sequence with its intermediates and final character.
+    Dcs(&'static [u8], char),
+}
+
+/// Sequences with built-in handling in the [`Processor`].
+const SUPPORTED_SEQUENCES: &[SequenceId] = &[
+    SequenceId::Csi(CsiCommand::InsertBlank),
+    SequenceId::Csi(CsiCommand::MoveUp),
+    SequenceId::Csi(CsiCommand::MoveDown),
+    SequenceId::Csi(CsiCommand::Repeat),
+    SequenceId::Csi(CsiCommand::MoveForward),
+    SequenceId::Csi(CsiCommand::IdentifyTerminal),
+    SequenceId::Csi(CsiCommand::MoveBackward),
+    SequenceId::Csi(CsiCommand::GotoLine),
+    SequenceId::Csi(CsiCommand::MoveDownAndCr),
+    SequenceId::Csi(CsiCommand::MoveUpAndCr),
+    SequenceId::Csi(CsiCommand::GotoCol),
+    SequenceId::Csi(CsiCommand::SetTabs),
+    SequenceId::Csi(CsiCommand::ClearTabs),
+    SequenceId::Csi(CsiCommand::Goto),
+    SequenceId::Csi(CsiCommand::SetMode),
+    SequenceId::Csi(CsiCommand::SetPrivateMode),
+    SequenceId::Csi(CsiCommand::MoveForwardTabs),
+    SequenceId::Csi(CsiCommand::ClearScreen),
+    SequenceId::Csi(CsiCommand::ClearLine),
+    SequenceId::Csi(CsiCommand::SetScp),
+    SequenceId::Csi(CsiCommand::InsertBlankLines),
+    SequenceId::Csi(CsiCommand::UnsetMode),
+    SequenceId::Csi(CsiCommand::UnsetPrivateMode),
+    SequenceId::Csi(CsiCommand::DeleteLines),
+    SequenceId::Csi(CsiCommand::TerminalAttribute),
+    SequenceId::Csi(CsiCommand::SetModifyOtherKeys),
+    SequenceId::Csi(CsiCommand::ReportModifyOtherKeys),
+    SequenceId::Csi(CsiCommand::DeviceStatus),
+    SequenceId::Csi(CsiCommand::DeviceStatusPrivate),
+    SequenceId::Csi(CsiCommand::DeleteChars),
+    SequenceId::Csi(CsiCommand::ReportMode),
+    SequenceId::Csi(CsiCommand::ReportPrivateMode),
+    SequenceId::Csi(CsiCommand::ReportTerminalVersion),
+    SequenceId::Csi(CsiCommand::SetCursorStyle),
+    SequenceId::Csi(CsiCommand::SetScrollingRegion),
+    SequenceId::Csi(CsiCommand::RestorePrivateModes),
+    SequenceId::Csi(CsiCommand::ScrollUp),
+    SequenceId::Csi(CsiCommand::SaveCursorPosition),
+    SequenceId::Csi(CsiCommand::SavePrivateModes),
+    SequenceId::Csi(CsiCommand::ScrollDown),
+    SequenceId::Csi(CsiCommand::WindowOps),
+    SequenceId::Csi(CsiCommand::SetWarningBellVolume),
+    SequenceId::Csi(CsiCommand::SetMarginBellVolume),
+    SequenceId::Csi(CsiCommand::ReportKeyboardMode),
+    SequenceId::Csi(CsiCommand::SetKeyboardMode),
+    SequenceId::Csi(CsiCommand::PushKeyboardMode),
+    SequenceId::Csi(CsiCommand::PopKeyboardModes),
+    SequenceId::Csi(CsiCommand::RestoreCursorPosition),
+    SequenceId::Csi(CsiCommand::MediaCopy),
+    SequenceId::Csi(CsiCommand::MediaCopyPrivate),
+    SequenceId::Csi(CsiCommand::PushSgr),
+    SequenceId::Csi(CsiCommand::PopSgr),
+    SequenceId::Csi(CsiCommand::SelectStatusDisplay),
+    SequenceId::Csi(CsiCommand::SetStatusDisplayType),
+    SequenceId::Csi(CsiCommand::SetConformanceLevel),
+    SequenceId::Csi(CsiCommand::SelectAttributeChangeExtent),
+    SequenceId::Csi(CsiCommand::SetTitleModes),
+    SequenceId::Csi(CsiCommand::ResetTitleModes),
+    SequenceId::Csi(CsiCommand::EraseChars),
+    SequenceId::Csi(CsiCommand::MoveBackwardTabs),
+    SequenceId::Osc(0),
+    SequenceId::Osc(2),
+    SequenceId::Osc(4),
+    SequenceId::Osc(8),
+    SequenceId::Osc(10),
+    SequenceId::Osc(11),
+    SequenceId::Osc(12),
+    SequenceId::Osc(13),
+    SequenceId::Osc(14),
+    SequenceId::Osc(15),
+    SequenceId::Osc(16),
+    SequenceId::Osc(17),
+    SequenceId::Osc(18),
+    SequenceId::Osc(19),
+    SequenceId::Osc(22),
+    SequenceId::Osc(50),
+    SequenceId::Osc(52),
+    SequenceId::Osc(104),
+    SequenceId::Osc(110),
+    SequenceId::Osc(111),
+    SequenceId::Osc(112),
+    SequenceId::Osc(113),
+    SequenceId::Osc(114),
+    SequenceId::Osc(115),
+    SequenceId::Osc(116),
+    SequenceId::Osc(117),
+    SequenceId::Osc(118),
+    SequenceId::Osc(119),
+    SequenceId::Osc(1337),
+    SequenceId::Esc(b"(", b'B'),
+    SequenceId::Esc(b")", b'B'),
+    SequenceId::Esc(b"*", b'B'),
+    SequenceId::Esc(b"+", b'B'),
+    SequenceId::Esc(b"(", b'A'),
+    SequenceId::Esc(b")", b'A'),
+    SequenceId::Esc(b"*", b'A'),
+    SequenceId::Esc(b"+", b'A'),
+    SequenceId::Esc(b"(", b'0'),
+    SequenceId::Esc(b")", b'0'),
+    SequenceId::Esc(b"*", b'0'),
+    SequenceId::Esc(b"+", b'0'),
+    SequenceId::Esc(b"", b'D'),
+    SequenceId::Esc(b"", b'E'),
+    SequenceId::Esc(b"", b'H'),
+    SequenceId::Esc(b"", b'M'),
+    SequenceId::Esc(b"", b'Z'),
+    SequenceId::Esc(b"", b'c'),
+    SequenceId::Esc(b"", b'7'),
+    SequenceId::Esc(b"", b'8'),
+    SequenceId::Esc(b"#", b'8'),
+    SequenceId::Esc(b" ", b'F'),
+    SequenceId::Esc(b" ", b'G'),
+    SequenceId::Esc(b" ", b'L'),
+    SequenceId::Esc(b" ", b'M'),
+    SequenceId::Esc(b" ", b'N'),
+    SequenceId::Esc(b"", b'='),
+    SequenceId::Esc(b"", b'>'),
+    SequenceId::Esc(b"", b'\\'),
+    SequenceId::Dcs(b"", 't'),
+    SequenceId::Dcs(b"$", 't'),
+    SequenceId::Dcs(b"$", 'p'),
+    SequenceId::Dcs(b"", '{'),
+];
+
+/// Sequences which the [`Processor`] dispatches to [`Handler`] methods.
+///
+/// This allows reporting the sequences a terminal could support. Sequences
+/// not listed here are passed to [`Handler::unhandled_csi`],
+/// [`Handler::unhandled_osc`] or [`Handler::unhandled_esc`]. Sequences only
+/// recognized in VT52 mode are not included, and listed sequences might still
+/// be unhandled due to unsupported parameters.
+pub fn supported_sequences() -> &'static [SequenceId] {
+    SUPPORTED_SEQUENCES
+}
+
 /// Wrapper for the ANSI modes.
 #[derive(Debug, Clone, Copy, Eq, PartialEq)]
 pub enum Mode {
@@ -967,10 +2486,69 @@ pub enum NamedPrivateMode {
     SyncUpdate = 2026,
 }
 
+/// State of a mode, as reported by DECRPM.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ModeState {
+    /// The mode is not recognized.
+    NotRecognized = 0,
+    /// The mode is set.
+    Set = 1,
+    /// The mode is reset.
+    Reset = 2,
+    /// The mode is permanently set.
+    PermanentlySet = 3,
+    /// The mode is permanently reset.
+    PermanentlyReset = 4,
+}
+
+/// Presentation state restored by DECRSPS.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum PresentationStateKind {
+    /// DECRSPS's parameter value of 1. Cursor information, as reported by
+    /// DECCIR.
+    CursorInformation,
+    /// DECRSPS's parameter value of 2. Tab stops, as reported by DECTABSR.
+    TabStops,
+}
+
+/// Terminal state restored by DECRSTS.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum TerminalStateKind {
+    /// DECRSTS's parameter value of 1. Terminal state, as reported by DECTSR.
+    TerminalState,
+    /// DECRSTS's parameter value of 2. Color table, as reported by DECCTR.
+    ColorTable,
+}
+
+/// Header of a DECDLD soft font definition.
+///
+/// Omitted parameters are `0`, which selects the terminal's default.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct SoftFontHeader {
+    /// Font number (`Pfn`).
+    pub font_number: u16,
+    /// First character to be redefined (`Pcn`).
+    pub starting_char: u16,
+    /// Which previously defined characters to erase (`Pe`).
+    pub erase_control: u16,
+    /// Character matrix width, or font size on the VT220 (`Pcmw`).
+    pub cell_width: u16,
+    /// Select 80 or 132 columns font width (`Pw`).
+    pub font_width: u16,
+    /// Text or full cell font (`Pt`).
+    pub text_or_full_cell: u16,
+    /// Character matrix height (`Pcmh`).
+    pub cell_height: u16,
+    /// Select 94 or 96 character set size (`Pcss`).
+    pub charset_size: u16,
+    /// Character set designator, including its intermediates (`Dscs`).
+    pub designator: Vec<u8>,
+}
+
 /// Mode for clearing line.
 ///
 /// Relative to cursor.
-#[derive(Debug)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum LineClearMode {
     /// Clear right of cursor.
     Right,
@@ -980,10 +2558,23 @@ pub enum LineClearMode {
     All,
 }
 
+impl TryFrom<u16> for LineClearMode {
+    type Error = ();
+
+    fn try_from(param: u16) -> Result<Self, ()> {
+        match param {
+            0 => Ok(LineClearMode::Right),
+            1 => Ok(LineClearMode::Left),
+            2 => Ok(LineClearMode::All),
+            _ => Err(()),
+        }
+    }
+}
+
 /// Mode for clearing terminal.
 ///
 /// Relative to cursor.
-#[derive(Debug)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum ClearMode {
     /// Clear below cursor.
     Below,
@@ -995,8 +2586,22 @@ pub enum ClearMode {
     Saved,
 }
 
+impl TryFrom<u16> for ClearMode {
+    type Error = ();
+
+    fn try_from(param: u16) -> Result<Self, ()> {
+        match param {
+            0 => Ok(ClearMode::Below),
+            1 => Ok(ClearMode::Above),
+            2 => Ok(ClearMode::All),
+            3 => Ok(ClearMode::Saved),
+            _ => Err(()),
+        }
+    }
+}
+
 /// Mode for clearing tab stops.
-#[derive(Debug)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum TabulationClearMode {
     /// Clear stop under cursor.
     Current,
@@ -1004,6 +2609,18 @@ pub enum TabulationClearMode {
     All,
 }
 
+impl TryFrom<u16> for TabulationClearMode {
+    type Error = ();
+
+    fn try_from(param: u16) -> Result<Self, ()> {
+        match param {
+            0 => Ok(TabulationClearMode::Current),
+            3 => Ok(TabulationClearMode::All),
+            _ => Err(()),
+        }
+    }
+}
+
 /// Standard colors.
 ///
 /// The order here matters since the enum should be castable to a `usize` for
@@ -1069,9 +2686,40 @@ pub enum NamedColor {
     BrightForeground,
     /// Dim foreground.
     DimForeground,
+    /// Foreground color of the mouse pointer.
+    PointerForeground,
+    /// Background color of the mouse pointer.
+    PointerBackground,
+    /// Tektronix foreground color.
+    TektronixForeground,
+    /// Tektronix background color.
+    TektronixBackground,
+    /// Background color of highlighted text.
+    HighlightBackground,
+    /// Tektronix cursor color.
+    TektronixCursor,
+    /// Foreground color of highlighted text.
+    HighlightForeground,
 }
 
 impl NamedColor {
+    /// Color corresponding to an XTerm dynamic color number (OSC 10-19).
+    fn from_dynamic_color(code: u8) -> Option<Self> {
+        match code {
+            10 => Some(NamedColor::Foreground),
+            11 => Some(NamedColor::Background),
+            12 => Some(NamedColor::Cursor),
+            13 => Some(NamedColor::PointerForeground),
+            14 => Some(NamedColor::PointerBackground),
+            15 => Some(NamedColor::TektronixForeground),
+            16 => Some(NamedColor::TektronixBackground),
+            17 => Some(NamedColor::HighlightBackground),
+            18 => Some(NamedColor::TektronixCursor),
+            19 => Some(NamedColor::HighlightForeground),
+            _ => None,
+        }
+    }
+
     #[must_use]
     pub fn to_bright(self) -> Self {
         match self {
@@ -1131,11 +2779,32 @@ pub enum Color {
     Indexed(u8),
 }
 
-/// Terminal character attributes.
-#[derive(Debug, Eq, PartialEq)]
-pub enum Attr {
-    /// Clear all special abilities.
-    Reset,
+impl Color {
+    /// Parse a color from any of its escape sequence spellings.
+    ///
+    /// This accepts the XParseColor `rgb:r/g/b` and `#rgb` forms used by OSC
+    /// sequences, as well as decimal color indices like `5`. Indices are
+    /// always returned as [`Color::Indexed`], matching `SGR 38 ; 5 ; Ps`.
+    ///
+    /// ```rust
+    /// use vte::ansi::{Color, Rgb};
+    ///
+    /// assert_eq!(Color::parse(b"#ff8000"), Some(Color::Spec(Rgb { r: 255, g: 128, b: 0 })));
+    /// assert_eq!(Color::parse(b"5"), Some(Color::Indexed(5)));
+    /// ```
+    pub fn parse(input: &[u8]) -> Option<Self> {
+        match input.first() {
+            Some(b'0'..=b'9') => parse_number(input).map(Color::Indexed),
+            _ => xparse_color(input).map(Color::Spec),
+        }
+    }
+}
+
+/// Terminal character attributes.
+#[derive(Debug, Eq, PartialEq)]
+pub enum Attr {
+    /// Clear all special abilities.
+    Reset,
     /// Bold text.
     Bold,
     /// Dim or secondary color.
@@ -1178,6 +2847,10 @@ pub enum Attr {
     CancelHidden,
     /// Cancel strikeout.
     CancelStrike,
+    /// Overlined text.
+    Overline,
+    /// Cancel overline.
+    CancelOverline,
     /// Set indexed foreground color.
     Foreground(Color),
     /// Set indexed background color.
@@ -1186,6 +2859,51 @@ pub enum Attr {
     UnderlineColor(Option<Color>),
 }
 
+impl Attr {
+    /// SGR parameters producing this attribute.
+    ///
+    /// Every item is one parameter, followed by its subparameters. Underline
+    /// styles like [`Attr::Undercurl`] use the `4:3` subparameter form, since
+    /// they have no representation without one.
+    ///
+    /// Attributes which cannot be represented as SGR parameters, like named
+    /// colors other than the 16 ANSI colors, return no parameters.
+    pub fn to_sgr(&self) -> Vec<Vec<u16>> {
+        let param: &[u16] = match self {
+            Attr::Reset => &[0],
+            Attr::Bold => &[1],
+            Attr::Dim => &[2],
+            Attr::Italic => &[3],
+            Attr::Underline => &[4],
+            Attr::DoubleUnderline => &[4, 2],
+            Attr::Undercurl => &[4, 3],
+            Attr::DottedUnderline => &[4, 4],
+            Attr::DashedUnderline => &[4, 5],
+            Attr::BlinkSlow => &[5],
+            Attr::BlinkFast => &[6],
+            Attr::Reverse => &[7],
+            Attr::Hidden => &[8],
+            Attr::Strike => &[9],
+            Attr::CancelBold => &[21],
+            Attr::CancelBoldDim => &[22],
+            Attr::CancelItalic => &[23],
+            Attr::CancelUnderline => &[24],
+            Attr::CancelBlink => &[25],
+            Attr::CancelReverse => &[27],
+            Attr::CancelHidden => &[28],
+            Attr::CancelStrike => &[29],
+            Attr::Overline => &[53],
+            Attr::CancelOverline => &[55],
+            Attr::UnderlineColor(None) => &[59],
+            Attr::Foreground(color) => return sgr_color_params(*color, 30),
+            Attr::Background(color) => return sgr_color_params(*color, 40),
+            Attr::UnderlineColor(Some(color)) => return sgr_color_params(*color, 50),
+        };
+
+        alloc::vec![param.to_vec()]
+    }
+}
+
 /// Identifiers which can be assigned to a graphic character set.
 #[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
 pub enum CharsetIndex {
@@ -1203,6 +2921,8 @@ pub enum StandardCharset {
     #[default]
     Ascii,
     SpecialCharacterAndLineDrawing,
+    /// United Kingdom national replacement character set.
+    Uk,
 }
 
 impl StandardCharset {
@@ -1247,6 +2967,10 @@ impl StandardCharset {
                 '~' => '·',
                 _ => c,
             },
+            StandardCharset::Uk => match c {
+                '#' => '£',
+                _ => c,
+            },
         }
     }
 }
@@ -1264,6 +2988,19 @@ pub enum ScpCharPath {
     RTL,
 }
 
+impl TryFrom<u16> for ScpCharPath {
+    type Error = ();
+
+    fn try_from(param: u16) -> Result<Self, ()> {
+        match param {
+            0 => Ok(ScpCharPath::Default),
+            1 => Ok(ScpCharPath::LTR),
+            2 => Ok(ScpCharPath::RTL),
+            _ => Err(()),
+        }
+    }
+}
+
 /// SCP control's second parameter which determines update mode/direction
 /// between components.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -1281,6 +3018,88 @@ pub enum ScpUpdateMode {
     PresentationToData,
 }
 
+impl TryFrom<u16> for ScpUpdateMode {
+    type Error = ();
+
+    fn try_from(param: u16) -> Result<Self, ()> {
+        match param {
+            0 => Ok(ScpUpdateMode::ImplementationDependant),
+            1 => Ok(ScpUpdateMode::DataToPresentation),
+            2 => Ok(ScpUpdateMode::PresentationToData),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Media copy mode, selecting the printer operation.
+///
+/// Some modes have a different meaning for the DEC private form (`CSI ? Ps
+/// i`), which is noted where applicable.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MediaCopyMode {
+    /// MC's parameter value of 0 (the default). Print the screen.
+    PrintScreen,
+    /// MC's parameter value of 1. Print the line containing the cursor.
+    PrintCursorLine,
+    /// MC's parameter value of 4. Turn off the printer controller mode, or
+    /// autoprint mode for the private form.
+    StopPrintLog,
+    /// MC's parameter value of 5. Turn on the printer controller mode, or
+    /// autoprint mode for the private form.
+    StartPrintLog,
+    /// MC's parameter value of 10. Print the composed display, or dump the
+    /// screen as HTML for the ANSI form.
+    PrintComposedDisplay,
+    /// MC's parameter value of 11. Print all pages, or dump the screen as
+    /// SVG for the ANSI form.
+    PrintAllPages,
+}
+
+/// Titles affected by the XTWINOPS title stack operations.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TitleTarget {
+    /// XTWINOPS's parameter value of 0 (the default). Icon and window title.
+    Both,
+    /// XTWINOPS's parameter value of 1. Icon title.
+    Icon,
+    /// XTWINOPS's parameter value of 2. Window title.
+    Window,
+}
+
+/// Display receiving output, selected by DECSASD.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum StatusDisplay {
+    /// DECSASD's parameter value of 0 (the default). Send output to the main
+    /// display.
+    Main,
+    /// DECSASD's parameter value of 1. Send output to the status line.
+    StatusLine,
+}
+
+/// Type of status line, selected by DECSSDT.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum StatusDisplayType {
+    /// DECSSDT's parameter value of 0 (the default). No status line.
+    None,
+    /// DECSSDT's parameter value of 1. Status line showing the terminal's
+    /// state.
+    Indicator,
+    /// DECSSDT's parameter value of 2. Status line written by the host
+    /// through DECSASD.
+    HostWritable,
+}
+
+/// Extent of rectangular attribute changes, selected by DECSACE.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AttributeChangeExtent {
+    /// DECSACE's parameter values of 0 (the default) and 1. Change the
+    /// characters from the start to the end position, wrapping at line ends.
+    Stream,
+    /// DECSACE's parameter value of 2. Change the characters inside the
+    /// rectangle spanned by the start and end position.
+    Rectangle,
+}
+
 impl<'a, H, T> crate::Perform for Performer<'a, H, T>
 where
     H: Handler + 'a,
@@ -1288,12 +3107,43 @@ where
 {
     #[inline]
     fn print(&mut self, c: char) {
+        // VT52 direct cursor addresses are offset by 32, to make them printable.
+        match self.state.vt52 {
+            Vt52State::AddressLine => {
+                let line = (c as usize).saturating_sub(0x20);
+                self.state.vt52 = Vt52State::AddressColumn(line);
+                return;
+            },
+            Vt52State::AddressColumn(line) => {
+                self.state.vt52 = Vt52State::Vt52;
+                self.state.visible_change = true;
+                self.handler.goto(line as i32, (c as usize).saturating_sub(0x20));
+                return;
+            },
+            _ => (),
+        }
+
+        self.state.visible_change = true;
+
+        #[cfg(feature = "wide-chars")]
+        match self.state.char_width {
+            Some(char_width) => self.handler.input_with_width(c, char_width(c)),
+            None => self.handler.input(c),
+        }
+        #[cfg(not(feature = "wide-chars"))]
         self.handler.input(c);
+
         self.state.preceding_char = Some(c);
     }
 
     #[inline]
     fn execute(&mut self, byte: u8) {
+        self.state.preceding_char = None;
+
+        if matches!(byte, C0::HT | C0::BS | C0::CR | C0::LF | C0::VT | C0::FF | C0::SUB) {
+            self.state.visible_change = true;
+        }
+
         match byte {
             C0::HT => self.handler.put_tab(1),
             C0::BS => self.handler.backspace(),
@@ -1309,219 +3159,135 @@ where
 
     #[inline]
     fn hook(&mut self, params: &Params, intermediates: &[u8], ignore: bool, action: char) {
-        debug!(
-            "[unhandled hook] params={:?}, ints: {:?}, ignore: {:?}, action: {:?}",
-            params, intermediates, ignore, action
-        );
-    }
-
-    #[inline]
-    fn put(&mut self, byte: u8) {
-        debug!("[unhandled put] byte={:?}", byte);
-    }
-
-    #[inline]
-    fn unhook(&mut self) {
-        debug!("[unhandled unhook]");
-    }
-
-    #[inline]
-    fn osc_dispatch(&mut self, params: &[&[u8]], bell_terminated: bool) {
-        let terminator = if bell_terminated { "\x07" } else { "\x1b\\" };
-
-        fn unhandled(params: &[&[u8]]) {
-            let mut buf = String::new();
-            for items in params {
-                buf.push('[');
-                for item in *items {
-                    let _ = write!(buf, "{:?}", *item as char);
-                }
-                buf.push_str("],");
-            }
-            debug!("[unhandled osc_dispatch]: [{}] at line {}", &buf, line!());
-        }
-
-        if params.is_empty() || params[0].is_empty() {
-            return;
-        }
-
-        match params[0] {
-            // Set window title.
-            b"0" | b"2" => {
-                if params.len() >= 2 {
-                    let title = params[1..]
-                        .iter()
-                        .flat_map(|x| str::from_utf8(x))
-                        .collect::<Vec<&str>>()
-                        .join(";")
-                        .trim()
-                        .to_owned();
-                    self.handler.set_title(Some(title));
-                    return;
-                }
-                unhandled(params);
-            },
-
-            // Set color index.
-            b"4" => {
-                if params.len() <= 1 || params.len() % 2 == 0 {
-                    unhandled(params);
-                    return;
-                }
-
-                for chunk in params[1..].chunks(2) {
-                    let index = match parse_number(chunk[0]) {
-                        Some(index) => index,
-                        None => {
-                            unhandled(params);
-                            continue;
-                        },
-                    };
-
-                    if let Some(c) = xparse_color(chunk[1]) {
-                        self.handler.set_color(index as usize, c);
-                    } else if chunk[1] == b"?" {
-                        let prefix = alloc::format!("4;{index}");
-                        self.handler.dynamic_color_sequence(prefix, index as usize, terminator);
-                    } else {
-                        unhandled(params);
-                    }
-                }
-            },
-
-            // Hyperlink.
-            b"8" if params.len() > 2 => {
-                let link_params = params[1];
-
-                // NOTE: The escape sequence is of form 'OSC 8 ; params ; URI ST', where
-                // URI is URL-encoded. However `;` is a special character and might be
-                // passed as is, thus we need to rebuild the URI.
-                let mut uri = str::from_utf8(params[2]).unwrap_or_default().to_string();
-                for param in params[3..].iter() {
-                    uri.push(';');
-                    uri.push_str(str::from_utf8(param).unwrap_or_default());
-                }
-
-                // The OSC 8 escape sequence must be stopped when getting an empty `uri`.
-                if uri.is_empty() {
-                    self.handler.set_hyperlink(None);
-                    return;
-                }
-
-                // Link parameters are in format of `key1=value1:key2=value2`. Currently only
-                // key `id` is defined.
-                let id = link_params
-                    .split(|&b| b == b':')
-                    .find_map(|kv| kv.strip_prefix(b"id="))
-                    .and_then(|kv| str::from_utf8(kv).ok().map(|e| e.to_owned()));
+        self.state.preceding_char = None;
 
-                self.handler.set_hyperlink(Some(Hyperlink { id, uri }));
+        match (action, intermediates) {
+            // Potential tmux passthrough, which is verified once the payload is complete.
+            ('t', []) if !ignore => self.state.dcs_buffer = Some((BufferedDcs::Tmux, Vec::new())),
+            ('t', [b'$']) if !ignore => {
+                let kind = match params.iter().next().map(|param| param[0]) {
+                    Some(1) => PresentationStateKind::CursorInformation,
+                    Some(2) => PresentationStateKind::TabStops,
+                    _ => return debug!("[unhandled DECRSPS] params={:?}", params),
+                };
+                self.state.dcs_buffer = Some((BufferedDcs::PresentationState(kind), Vec::new()));
             },
-
-            // Get/set Foreground, Background, Cursor colors.
-            b"10" | b"11" | b"12" => {
-                if params.len() >= 2 {
-                    if let Some(mut dynamic_code) = parse_number(params[0]) {
-                        for param in &params[1..] {
-                            // 10 is the first dynamic color, also the foreground.
-                            let offset = dynamic_code as usize - 10;
-                            let index = NamedColor::Foreground as usize + offset;
-
-                            // End of setting dynamic colors.
-                            if index > NamedColor::Cursor as usize {
-                                unhandled(params);
-                                break;
-                            }
-
-                            if let Some(color) = xparse_color(param) {
-                                self.handler.set_color(index, color);
-                            } else if param == b"?" {
-                                self.handler.dynamic_color_sequence(
-                                    dynamic_code.to_string(),
-                                    index,
-                                    terminator,
-                                );
-                            } else {
-                                unhandled(params);
-                            }
-                            dynamic_code += 1;
-                        }
-                        return;
-                    }
-                }
-                unhandled(params);
+            ('p', [b'$']) if !ignore => {
+                let kind = match params.iter().next().map(|param| param[0]) {
+                    Some(1) => TerminalStateKind::TerminalState,
+                    Some(2) => TerminalStateKind::ColorTable,
+                    _ => return debug!("[unhandled DECRSTS] params={:?}", params),
+                };
+                self.state.dcs_buffer = Some((BufferedDcs::TerminalState(kind), Vec::new()));
             },
-
-            // Set mouse cursor shape.
-            b"22" if params.len() == 2 => {
-                let shape = String::from_utf8_lossy(params[1]);
-                match CursorIcon::from_str(&shape) {
-                    Ok(cursor_icon) => self.handler.set_mouse_cursor_icon(cursor_icon),
-                    Err(_) => debug!("[osc 22] unrecognized cursor icon shape: {shape:?}"),
-                }
+            ('{', []) if !ignore => {
+                let mut params = params.iter().map(|param| param[0]);
+                let mut next_param = || params.next().unwrap_or(0);
+                let header = SoftFontHeader {
+                    font_number: next_param(),
+                    starting_char: next_param(),
+                    erase_control: next_param(),
+                    cell_width: next_param(),
+                    font_width: next_param(),
+                    text_or_full_cell: next_param(),
+                    cell_height: next_param(),
+                    charset_size: next_param(),
+                    designator: Vec::new(),
+                };
+                self.state.soft_font = Some(SoftFontState::Designator(header));
             },
+            _ => debug!(
+                "[unhandled hook] params={:?}, ints: {:?}, ignore: {:?}, action: {:?}",
+                params, intermediates, ignore, action
+            ),
+        }
+    }
 
-            // Set cursor style.
-            b"50" => {
-                if params.len() >= 2
-                    && params[1].len() >= 13
-                    && params[1][0..12] == *b"CursorShape="
-                {
-                    let shape = match params[1][12] as char {
-                        '0' => CursorShape::Block,
-                        '1' => CursorShape::Beam,
-                        '2' => CursorShape::Underline,
-                        _ => return unhandled(params),
-                    };
-                    self.handler.set_cursor_shape(shape);
-                    return;
+    #[inline]
+    fn put(&mut self, byte: u8) {
+        match &mut self.state.soft_font {
+            Some(SoftFontState::Data) => return self.handler.soft_font_put(byte),
+            // The designator consists of up to two intermediates and a final byte.
+            Some(SoftFontState::Designator(header)) => {
+                header.designator.push(byte);
+                match byte {
+                    0x20..=0x2F if header.designator.len() < 3 => (),
+                    0x30..=0x7E => {
+                        let header = mem::take(header);
+                        self.state.soft_font = Some(SoftFontState::Data);
+                        self.handler.define_soft_font(header);
+                    },
+                    _ => {
+                        debug!("[unhandled soft font] designator={:?}", header.designator);
+                        self.state.soft_font = None;
+                    },
                 }
-                unhandled(params);
+                return;
             },
+            None => (),
+        }
 
-            // Set clipboard.
-            b"52" => {
-                if params.len() < 3 {
-                    return unhandled(params);
-                }
+        self.state.push_dcs_byte(byte);
+
+        // Once the tmux prefix is complete, the processor takes over unescaping the
+        // payload.
+        if let Some((BufferedDcs::Tmux, prefix)) = &self.state.dcs_buffer {
+            if prefix == b"mux;" {
+                self.state.dcs_buffer = Some((BufferedDcs::TmuxPassthrough, Vec::new()));
+                self.state.tmux_passthrough = Some(TmuxPassthroughState::Payload);
+                self.terminated = true;
+            } else if !b"mux;".starts_with(prefix) {
+                debug!("[unhandled dcs] payload={:?}", prefix);
+                self.state.dcs_buffer = None;
+            }
+        }
+    }
 
-                let clipboard = params[1].first().unwrap_or(&b'c');
-                match params[2] {
-                    b"?" => self.handler.clipboard_load(*clipboard, terminator),
-                    base64 => self.handler.clipboard_store(*clipboard, base64),
-                }
+    #[inline]
+    fn unhook(&mut self) {
+        match self.state.soft_font.take() {
+            Some(SoftFontState::Data) => return self.handler.soft_font_end(false),
+            Some(SoftFontState::Designator(header)) => {
+                return debug!("[unhandled soft font] designator={:?}", header.designator);
             },
+            None => (),
+        }
 
-            // Reset color index.
-            b"104" => {
-                // Reset all color indexes when no parameters are given.
-                if params.len() == 1 || params[1].is_empty() {
-                    for i in 0..256 {
-                        self.handler.reset_color(i);
-                    }
-                    return;
-                }
+        let (kind, buffer) = match self.state.dcs_buffer.take() {
+            Some(dcs_buffer) => dcs_buffer,
+            None => return debug!("[unhandled unhook]"),
+        };
 
-                // Reset color indexes given as parameters.
-                for param in &params[1..] {
-                    match parse_number(param) {
-                        Some(index) => self.handler.reset_color(index as usize),
-                        None => unhandled(params),
-                    }
-                }
+        match kind {
+            BufferedDcs::TmuxPassthrough => self.handler.tmux_passthrough(&buffer),
+            BufferedDcs::PresentationState(kind) => {
+                self.handler.restore_presentation_state(kind, &buffer)
             },
+            BufferedDcs::TerminalState(kind) => self.handler.restore_terminal_state(kind, &buffer),
+            BufferedDcs::Tmux => debug!("[unhandled dcs] payload={:?}", buffer),
+        }
+    }
 
-            // Reset foreground color.
-            b"110" => self.handler.reset_color(NamedColor::Foreground as usize),
-
-            // Reset background color.
-            b"111" => self.handler.reset_color(NamedColor::Background as usize),
-
-            // Reset text cursor color.
-            b"112" => self.handler.reset_color(NamedColor::Cursor as usize),
-
-            _ => unhandled(params),
+    #[inline]
+    fn string_aborted(&mut self, _byte: u8) {
+        if let Some(SoftFontState::Data) = self.state.soft_font.take() {
+            self.handler.soft_font_end(true);
         }
+        self.state.dcs_buffer = None;
+    }
+
+    #[inline]
+    fn osc_dispatch(&mut self, params: &[&[u8]], bell_terminated: bool) {
+        self.state.preceding_char = None;
+
+        let preserve_title_whitespace = self.state.preserve_title_whitespace;
+        dispatch_osc_internal(
+            self.handler,
+            params,
+            bell_terminated,
+            preserve_title_whitespace,
+            &mut self.state.visible_change,
+        );
     }
 
     #[allow(clippy::cognitive_complexity)]
@@ -1533,20 +3299,48 @@ where
         has_ignored_intermediates: bool,
         action: char,
     ) {
+        // REP only applies to graphic characters directly preceding it.
+        let preceding_char = self.state.preceding_char.take();
+
         macro_rules! unhandled {
             () => {{
                 debug!(
                     "[Unhandled CSI] action={:?}, params={:?}, intermediates={:?}",
                     action, params, intermediates
                 );
+                self.handler.unhandled_csi(action, params, intermediates);
             }};
         }
 
-        if has_ignored_intermediates || intermediates.len() > 2 {
+        // Convert a parameter, ignoring the sequence if it is invalid.
+        macro_rules! try_param {
+            ($param:expr) => {
+                match $param.try_into() {
+                    Ok(value) => value,
+                    Err(_) => {
+                        unhandled!();
+                        return;
+                    },
+                }
+            };
+        }
+
+        // VT52 mode has no control sequences.
+        if has_ignored_intermediates || intermediates.len() > 2 || self.state.vt52.is_vt52() {
             unhandled!();
             return;
         }
 
+        // Synchronized updates are reported separately by `advance_tracked`.
+        let command = CsiCommand::parse(action, intermediates);
+        let sync_mode =
+            matches!(command, CsiCommand::SetPrivateMode | CsiCommand::UnsetPrivateMode)
+                && params.len() == 1
+                && matches!(params.iter().next(), Some([2026]));
+        if command.changes_screen() && !sync_mode {
+            self.state.visible_change = true;
+        }
+
         let mut params_iter = params.iter();
         let handler = &mut self.handler;
 
@@ -1555,52 +3349,43 @@ where
             _ => default,
         };
 
-        match (action, intermediates) {
-            ('@', []) => handler.insert_blank(next_param_or(1) as usize),
-            ('A', []) => handler.move_up(next_param_or(1) as usize),
-            ('B', []) | ('e', []) => handler.move_down(next_param_or(1) as usize),
-            ('b', []) => {
-                if let Some(c) = self.state.preceding_char {
-                    for _ in 0..next_param_or(1) {
-                        handler.input(c);
-                    }
+        match command {
+            CsiCommand::InsertBlank => handler.insert_blank(next_param_or(1) as usize),
+            CsiCommand::MoveUp => handler.move_cursor(0, -i32::from(next_param_or(1))),
+            CsiCommand::MoveDown => handler.move_cursor(0, i32::from(next_param_or(1))),
+            CsiCommand::Repeat => {
+                if let Some(c) = preceding_char {
+                    handler.repeat_preceding(next_param_or(1) as usize, c);
                 } else {
                     debug!("tried to repeat with no preceding char");
                 }
             },
-            ('C', []) | ('a', []) => handler.move_forward(next_param_or(1) as usize),
-            ('c', intermediates) if next_param_or(0) == 0 => {
+            CsiCommand::MoveForward => handler.move_cursor(i32::from(next_param_or(1)), 0),
+            CsiCommand::IdentifyTerminal if next_param_or(0) == 0 => {
                 handler.identify_terminal(intermediates.first().map(|&i| i as char))
             },
-            ('D', []) => handler.move_backward(next_param_or(1) as usize),
-            ('d', []) => handler.goto_line(next_param_or(1) as i32 - 1),
-            ('E', []) => handler.move_down_and_cr(next_param_or(1) as usize),
-            ('F', []) => handler.move_up_and_cr(next_param_or(1) as usize),
-            ('G', []) | ('`', []) => handler.goto_col(next_param_or(1) as usize - 1),
-            ('W', [b'?']) if next_param_or(0) == 5 => handler.set_tabs(8),
-            ('g', []) => {
-                let mode = match next_param_or(0) {
-                    0 => TabulationClearMode::Current,
-                    3 => TabulationClearMode::All,
-                    _ => {
-                        unhandled!();
-                        return;
-                    },
-                };
-
+            CsiCommand::MoveBackward => handler.move_cursor(-i32::from(next_param_or(1)), 0),
+            CsiCommand::GotoLine => handler.goto_line(next_param_or(1) as i32 - 1),
+            CsiCommand::MoveDownAndCr => handler.move_down_and_cr(next_param_or(1) as usize),
+            CsiCommand::MoveUpAndCr => handler.move_up_and_cr(next_param_or(1) as usize),
+            CsiCommand::GotoCol => handler.goto_col(next_param_or(1) as usize - 1),
+            CsiCommand::SetTabs if next_param_or(0) == 5 => handler.set_tabs(8),
+            CsiCommand::ClearTabs => {
+                let mode = try_param!(next_param_or(0));
                 handler.clear_tabs(mode);
             },
-            ('H', []) | ('f', []) => {
+            CsiCommand::Goto => {
                 let y = next_param_or(1) as i32;
                 let x = next_param_or(1) as usize;
                 handler.goto(y - 1, x - 1);
             },
-            ('h', []) => {
+            CsiCommand::SetMode => {
                 for param in params_iter.map(|param| param[0]) {
                     handler.set_mode(Mode::new(param))
                 }
             },
-            ('h', [b'?']) => {
+            CsiCommand::SetPrivateMode => {
+                let mut unknown_modes = Vec::new();
                 for param in params_iter.map(|param| param[0]) {
                     // Handle sync updates opaquely.
                     if param == NamedPrivateMode::SyncUpdate as u16 {
@@ -1608,81 +3393,96 @@ where
                         self.terminated = true;
                     }
 
-                    handler.set_private_mode(PrivateMode::new(param))
+                    if let Some(state) =
+                        self.state.tracked_modes.as_mut().and_then(|modes| modes.get_mut(param))
+                    {
+                        *state = true;
+                    }
+
+                    let mode = PrivateMode::new(param);
+                    if let PrivateMode::Unknown(mode) = mode {
+                        unknown_modes.push(mode);
+                    }
+
+                    handler.set_private_mode(mode)
                 }
-            },
-            ('I', []) => handler.move_forward_tabs(next_param_or(1)),
-            ('J', []) => {
-                let mode = match next_param_or(0) {
-                    0 => ClearMode::Below,
-                    1 => ClearMode::Above,
-                    2 => ClearMode::All,
-                    3 => ClearMode::Saved,
-                    _ => {
-                        unhandled!();
-                        return;
-                    },
-                };
 
-                handler.clear_screen(mode);
+                if !unknown_modes.is_empty() {
+                    handler.unknown_private_modes(&unknown_modes);
+                }
+            },
+            CsiCommand::MoveForwardTabs => handler.move_forward_tabs(next_param_or(1)),
+            CsiCommand::ClearScreen => match try_param!(next_param_or(0)) {
+                ClearMode::Saved => handler.clear_scrollback(),
+                mode => handler.clear_screen(mode),
+            },
+            CsiCommand::ClearLine => {
+                let mode = try_param!(next_param_or(0));
+                handler.clear_line(mode);
             },
-            ('K', []) => {
+            command @ (CsiCommand::MediaCopy | CsiCommand::MediaCopyPrivate) => {
                 let mode = match next_param_or(0) {
-                    0 => LineClearMode::Right,
-                    1 => LineClearMode::Left,
-                    2 => LineClearMode::All,
+                    0 => MediaCopyMode::PrintScreen,
+                    1 => MediaCopyMode::PrintCursorLine,
+                    4 => MediaCopyMode::StopPrintLog,
+                    5 => MediaCopyMode::StartPrintLog,
+                    10 => MediaCopyMode::PrintComposedDisplay,
+                    11 => MediaCopyMode::PrintAllPages,
                     _ => {
                         unhandled!();
                         return;
                     },
                 };
 
-                handler.clear_line(mode);
+                handler.media_copy(mode, command == CsiCommand::MediaCopyPrivate);
             },
-            ('k', [b' ']) => {
+            CsiCommand::SetScp => {
                 // SCP control.
-                let char_path = match next_param_or(0) {
-                    0 => ScpCharPath::Default,
-                    1 => ScpCharPath::LTR,
-                    2 => ScpCharPath::RTL,
-                    _ => {
-                        unhandled!();
-                        return;
-                    },
-                };
-
-                let update_mode = match next_param_or(0) {
-                    0 => ScpUpdateMode::ImplementationDependant,
-                    1 => ScpUpdateMode::DataToPresentation,
-                    2 => ScpUpdateMode::PresentationToData,
-                    _ => {
-                        unhandled!();
-                        return;
-                    },
-                };
-
+                let char_path = try_param!(next_param_or(0));
+                let update_mode = try_param!(next_param_or(0));
                 handler.set_scp(char_path, update_mode);
             },
-            ('L', []) => handler.insert_blank_lines(next_param_or(1) as usize),
-            ('l', []) => {
+            CsiCommand::InsertBlankLines => handler.insert_blank_lines(next_param_or(1) as usize),
+            CsiCommand::UnsetMode => {
                 for param in params_iter.map(|param| param[0]) {
                     handler.unset_mode(Mode::new(param))
                 }
             },
-            ('l', [b'?']) => {
+            CsiCommand::UnsetPrivateMode => {
+                let mut unknown_modes = Vec::new();
                 for param in params_iter.map(|param| param[0]) {
-                    handler.unset_private_mode(PrivateMode::new(param))
+                    // Reset DECANM to enter VT52 mode.
+                    if param == 2 && self.state.vt52 == Vt52State::Ansi {
+                        self.state.vt52 = Vt52State::Vt52;
+                    }
+
+                    if let Some(state) =
+                        self.state.tracked_modes.as_mut().and_then(|modes| modes.get_mut(param))
+                    {
+                        *state = false;
+                    }
+
+                    let mode = PrivateMode::new(param);
+                    if let PrivateMode::Unknown(mode) = mode {
+                        unknown_modes.push(mode);
+                    }
+
+                    handler.unset_private_mode(mode)
+                }
+
+                if !unknown_modes.is_empty() {
+                    handler.unknown_private_modes(&unknown_modes);
                 }
             },
-            ('M', []) => handler.delete_lines(next_param_or(1) as usize),
-            ('m', []) => {
+            CsiCommand::DeleteLines => handler.delete_lines(next_param_or(1) as usize),
+            CsiCommand::TerminalAttribute => {
                 if params.is_empty() {
                     handler.terminal_attribute(Attr::Reset);
                 } else {
                     attrs_from_sgr_parameters(*handler, &mut params_iter);
                 }
             },
-            ('m', [b'>']) => {
+            CsiCommand::SetModifyOtherKeys => {
                 let mode = match (next_param_or(1) == 4).then(|| next_param_or(0)) {
                     Some(0) => ModifyOtherKeys::Reset,
                     Some(1) => ModifyOtherKeys::EnableExceptWellDefined,
@@ -1691,60 +3491,102 @@ where
                 };
                 handler.set_modify_other_keys(mode);
             },
-            ('m', [b'?']) => {
+            CsiCommand::ReportModifyOtherKeys => {
                 if params_iter.next() == Some(&[4]) {
                     handler.report_modify_other_keys();
                 } else {
                     unhandled!()
                 }
             },
-            ('n', []) => handler.device_status(next_param_or(0) as usize),
-            ('P', []) => handler.delete_chars(next_param_or(1) as usize),
-            ('p', [b'$']) => {
-                let mode = next_param_or(0);
-                handler.report_mode(Mode::new(mode));
+            CsiCommand::DeviceStatus => handler.device_status(next_param_or(0) as usize),
+            CsiCommand::DeviceStatusPrivate => {
+                handler.device_status_private(next_param_or(0) as usize)
+            },
+            CsiCommand::DeleteChars => handler.delete_chars(next_param_or(1) as usize),
+            CsiCommand::ReportMode => {
+                for param in params_iter.map(|param| param[0]) {
+                    handler.report_mode(Mode::new(param));
+                }
+            },
+            CsiCommand::ReportPrivateMode => {
+                for param in params_iter.map(|param| param[0]) {
+                    let mode = PrivateMode::new(param);
+                    match self.state.tracked_modes.as_mut().and_then(|modes| modes.get_mut(param)) {
+                        Some(true) => handler.report_private_mode_state(mode, ModeState::Set),
+                        Some(false) => handler.report_private_mode_state(mode, ModeState::Reset),
+                        None => handler.report_private_mode(mode),
+                    }
+                }
             },
-            ('p', [b'?', b'$']) => {
-                let mode = next_param_or(0);
-                handler.report_private_mode(PrivateMode::new(mode));
+            CsiCommand::ReportTerminalVersion if next_param_or(0) == 0 => {
+                handler.report_terminal_version()
             },
-            ('q', [b' ']) => {
+            CsiCommand::SetCursorStyle => {
                 // DECSCUSR (CSI Ps SP q) -- Set Cursor Style.
                 let cursor_style_id = next_param_or(0);
                 let shape = match cursor_style_id {
                     0 => None,
-                    1 | 2 => Some(CursorShape::Block),
-                    3 | 4 => Some(CursorShape::Underline),
-                    5 | 6 => Some(CursorShape::Beam),
-                    _ => {
-                        unhandled!();
-                        return;
-                    },
+                    id => Some(try_param!(id)),
                 };
                 let cursor_style =
                     shape.map(|shape| CursorStyle { shape, blinking: cursor_style_id % 2 == 1 });
 
                 handler.set_cursor_style(cursor_style);
             },
-            ('r', []) => {
+            CsiCommand::SetScrollingRegion => {
                 let top = next_param_or(1) as usize;
                 let bottom =
                     params_iter.next().map(|param| param[0] as usize).filter(|&param| param != 0);
 
-                handler.set_scrolling_region(top, bottom);
+                match bottom {
+                    Some(bottom) if top >= bottom => unhandled!(),
+                    None if top == 1 => handler.reset_scrolling_region(),
+                    _ => handler.set_scrolling_region(top, bottom),
+                }
+            },
+            CsiCommand::ScrollUp => handler.scroll_up(next_param_or(1) as usize),
+            CsiCommand::RestorePrivateModes => {
+                let modes: Vec<_> = params_iter.map(|param| PrivateMode::new(param[0])).collect();
+                handler.restore_private_modes(&modes);
+            },
+            CsiCommand::SaveCursorPosition => handler.save_cursor_position(),
+            CsiCommand::SavePrivateModes => {
+                let modes: Vec<_> = params_iter.map(|param| PrivateMode::new(param[0])).collect();
+                handler.save_private_modes(&modes);
             },
-            ('S', []) => handler.scroll_up(next_param_or(1) as usize),
-            ('s', []) => handler.save_cursor_position(),
-            ('T', []) => handler.scroll_down(next_param_or(1) as usize),
-            ('t', []) => match next_param_or(1) as usize {
+            CsiCommand::ScrollDown => handler.scroll_down(next_param_or(1) as usize),
+            CsiCommand::WindowOps => match next_param_or(1) as usize {
                 14 => handler.text_area_size_pixels(),
                 18 => handler.text_area_size_chars(),
-                22 => handler.push_title(),
-                23 => handler.pop_title(),
+                ops @ (22 | 23) => {
+                    let target = match next_param_or(0) {
+                        0 => TitleTarget::Both,
+                        1 => TitleTarget::Icon,
+                        2 => TitleTarget::Window,
+                        _ => {
+                            unhandled!();
+                            return;
+                        },
+                    };
+
+                    if ops == 22 {
+                        handler.push_title(target);
+                    } else {
+                        handler.pop_title(target);
+                    }
+                },
+                _ => unhandled!(),
+            },
+            CsiCommand::SetWarningBellVolume => match next_param_or(0) {
+                level @ 0..=8 => handler.set_warning_bell_volume(level as u8),
+                _ => unhandled!(),
+            },
+            CsiCommand::SetMarginBellVolume => match next_param_or(0) {
+                level @ 0..=8 => handler.set_margin_bell_volume(level as u8),
                 _ => unhandled!(),
             },
-            ('u', [b'?']) => handler.report_keyboard_mode(),
-            ('u', [b'=']) => {
+            CsiCommand::ReportKeyboardMode => handler.report_keyboard_mode(),
+            CsiCommand::SetKeyboardMode => {
                 let mode = KeyboardModes::from_bits_truncate(next_param_or(0) as u8);
                 let behavior = match next_param_or(1) {
                     3 => KeyboardModesApplyBehavior::Difference,
@@ -1754,35 +3596,131 @@ where
                 };
                 handler.set_keyboard_mode(mode, behavior);
             },
-            ('u', [b'>']) => {
+            CsiCommand::PushKeyboardMode => {
                 let mode = KeyboardModes::from_bits_truncate(next_param_or(0) as u8);
                 handler.push_keyboard_mode(mode);
             },
-            ('u', [b'<']) => {
+            CsiCommand::PopKeyboardModes => {
                 // The default is 1.
                 handler.pop_keyboard_modes(next_param_or(1));
             },
-            ('u', []) => handler.restore_cursor_position(),
-            ('X', []) => handler.erase_chars(next_param_or(1) as usize),
-            ('Z', []) => handler.move_backward_tabs(next_param_or(1)),
-            _ => unhandled!(),
-        }
-    }
-
-    #[inline]
-    fn esc_dispatch(&mut self, intermediates: &[u8], _ignore: bool, byte: u8) {
-        macro_rules! unhandled {
-            () => {{
-                debug!(
-                    "[unhandled] esc_dispatch ints={:?}, byte={:?} ({:02x})",
-                    intermediates, byte as char, byte
-                );
-            }};
-        }
+            CsiCommand::RestoreCursorPosition => handler.restore_cursor_position(),
+            // Without explicit items, all attributes are pushed.
+            CsiCommand::PushSgr if params.iter().all(|param| param[0] == 0) => {
+                handler.push_sgr(None)
+            },
+            CsiCommand::PushSgr => {
+                let items = params_iter
+                    .filter_map(|param| SgrStackItems::from_param(param[0]))
+                    .fold(SgrStackItems::empty(), |items, item| items | item);
+                handler.push_sgr(Some(items));
+            },
+            CsiCommand::PopSgr => handler.pop_sgr(),
+            command @ (CsiCommand::SetTitleModes | CsiCommand::ResetTitleModes) => {
+                let mut modes = TitleModes::empty();
+                for param in params_iter {
+                    match TitleModes::from_param(param[0]) {
+                        Some(mode) => modes |= mode,
+                        None => {
+                            unhandled!();
+                            return;
+                        },
+                    }
+                }
 
-        macro_rules! configure_charset {
-            ($charset:path, $intermediates:expr) => {{
-                let index: CharsetIndex = match $intermediates {
+                if command == CsiCommand::SetTitleModes {
+                    handler.set_title_modes(modes);
+                } else {
+                    handler.reset_title_modes(modes);
+                }
+            },
+            CsiCommand::SetConformanceLevel => {
+                let level = match next_param_or(0) {
+                    level @ 61..=65 => (level - 60) as u8,
+                    _ => {
+                        unhandled!();
+                        return;
+                    },
+                };
+
+                // VT100 compatibility only supports 7-bit controls.
+                let eight_bit = level > 1 && next_param_or(0) != 1;
+
+                handler.set_conformance_level(level, eight_bit);
+            },
+            CsiCommand::SelectStatusDisplay => {
+                let display = match next_param_or(0) {
+                    0 => StatusDisplay::Main,
+                    1 => StatusDisplay::StatusLine,
+                    _ => {
+                        unhandled!();
+                        return;
+                    },
+                };
+
+                handler.select_status_display(display);
+            },
+            CsiCommand::SetStatusDisplayType => {
+                let kind = match next_param_or(0) {
+                    0 => StatusDisplayType::None,
+                    1 => StatusDisplayType::Indicator,
+                    2 => StatusDisplayType::HostWritable,
+                    _ => {
+                        unhandled!();
+                        return;
+                    },
+                };
+
+                handler.set_status_display_type(kind);
+            },
+            CsiCommand::SelectAttributeChangeExtent => {
+                let extent = match next_param_or(0) {
+                    0 | 1 => AttributeChangeExtent::Stream,
+                    2 => AttributeChangeExtent::Rectangle,
+                    _ => {
+                        unhandled!();
+                        return;
+                    },
+                };
+
+                handler.select_attribute_change_extent(extent);
+            },
+            CsiCommand::EraseChars => handler.erase_chars(next_param_or(1) as usize),
+            CsiCommand::MoveBackwardTabs => handler.move_backward_tabs(next_param_or(1)),
+            // Sequences with unsupported parameters are unhandled.
+            CsiCommand::IdentifyTerminal
+            | CsiCommand::SetTabs
+            | CsiCommand::ReportTerminalVersion
+            | CsiCommand::Unknown => unhandled!(),
+        }
+    }
+
+    #[inline]
+    fn esc_dispatch(&mut self, intermediates: &[u8], _ignore: bool, byte: u8) {
+        self.state.preceding_char = None;
+
+        // Index, reset, DECALN, restoring the cursor and all VT52 escapes.
+        // Keypad and ANSI mode changes are not visible in either mode.
+        self.state.visible_change |= match (byte, intermediates) {
+            (b'=' | b'>' | b'<', []) => false,
+            (_, []) if self.state.vt52.is_vt52() => true,
+            (b'D' | b'E' | b'M' | b'c' | b'8', []) | (b'8', [b'#']) => true,
+            _ => false,
+        };
+
+        macro_rules! unhandled {
+            () => {{
+                debug!(
+                    "[unhandled] esc_dispatch ints={:?}, byte={:?} ({:02x})",
+                    intermediates, byte as char, byte
+                );
+                self.handler.unhandled_esc(intermediates, byte);
+            }};
+        }
+
+        macro_rules! configure_charset {
+            ($charset:path, $intermediates:expr) => {{
+                let index: CharsetIndex = match $intermediates {
                     [b'('] => CharsetIndex::G0,
                     [b')'] => CharsetIndex::G1,
                     [b'*'] => CharsetIndex::G2,
@@ -1796,7 +3734,43 @@ where
             }};
         }
 
+        if self.state.vt52.is_vt52() {
+            // Escapes abort incomplete direct cursor addresses.
+            self.state.vt52 = Vt52State::Vt52;
+
+            match (byte, intermediates) {
+                (b'A', []) => self.handler.move_up(1),
+                (b'B', []) => self.handler.move_down(1),
+                (b'C', []) => self.handler.move_forward(1),
+                (b'D', []) => self.handler.move_backward(1),
+                (b'F', []) => self.handler.configure_charset(
+                    CharsetIndex::G0,
+                    StandardCharset::SpecialCharacterAndLineDrawing,
+                ),
+                (b'G', []) => {
+                    self.handler.configure_charset(CharsetIndex::G0, StandardCharset::Ascii)
+                },
+                (b'H', []) => self.handler.goto(0, 0),
+                (b'I', []) => self.handler.reverse_index(),
+                (b'J', []) => self.handler.clear_screen(ClearMode::Below),
+                (b'K', []) => self.handler.clear_line(LineClearMode::Right),
+                (b'Y', []) => self.state.vt52 = Vt52State::AddressLine,
+                (b'Z', []) => self.handler.identify_terminal_vt52(),
+                (b'=', []) => self.handler.set_keypad_application_mode(),
+                (b'>', []) => self.handler.unset_keypad_application_mode(),
+                // Set DECANM to return to ANSI mode.
+                (b'<', []) => {
+                    self.state.vt52 = Vt52State::Ansi;
+                    self.handler.set_private_mode(PrivateMode::new(2));
+                },
+                _ => unhandled!(),
+            }
+
+            return;
+        }
+
         match (byte, intermediates) {
+            (b'A', intermediates) => configure_charset!(StandardCharset::Uk, intermediates),
             (b'B', intermediates) => configure_charset!(StandardCharset::Ascii, intermediates),
             (b'D', []) => self.handler.linefeed(),
             (b'E', []) => {
@@ -1806,13 +3780,23 @@ where
             (b'H', []) => self.handler.set_horizontal_tabstop(),
             (b'M', []) => self.handler.reverse_index(),
             (b'Z', []) => self.handler.identify_terminal(None),
-            (b'c', []) => self.handler.reset_state(),
+            (b'c', []) => {
+                if let Some(tracked_modes) = &mut self.state.tracked_modes {
+                    *tracked_modes = TrackedModes::default();
+                }
+                self.handler.reset_state();
+            },
             (b'0', intermediates) => {
                 configure_charset!(StandardCharset::SpecialCharacterAndLineDrawing, intermediates)
             },
             (b'7', []) => self.handler.save_cursor_position(),
             (b'8', [b'#']) => self.handler.decaln(),
             (b'8', []) => self.handler.restore_cursor_position(),
+            (b'F', [b' ']) => self.handler.set_c1_transmission(false),
+            (b'G', [b' ']) => self.handler.set_c1_transmission(true),
+            (b'L', [b' ']) => self.handler.set_conformance_level(1, false),
+            (b'M', [b' ']) => self.handler.set_conformance_level(2, false),
+            (b'N', [b' ']) => self.handler.set_conformance_level(3, false),
             (b'=', []) => self.handler.set_keypad_application_mode(),
             (b'>', []) => self.handler.unset_keypad_application_mode(),
             // String terminator, do nothing (parser handles as string terminator).
@@ -1827,6 +3811,273 @@ where
     }
 }
 
+/// Interpret an OSC, dispatching it to the matching [`Handler`] methods.
+///
+/// This is the OSC handling of the [`Processor`], which allows handling OSC
+/// semantics from a custom [`Perform`] implementation by calling it from
+/// [`Perform::osc_dispatch`]. Window titles are trimmed.
+///
+/// [`Perform`]: crate::Perform
+/// [`Perform::osc_dispatch`]: crate::Perform::osc_dispatch
+pub fn dispatch_osc<H: Handler>(handler: &mut H, params: &[&[u8]], bell_terminated: bool) {
+    dispatch_osc_internal(handler, params, bell_terminated, false, &mut false);
+}
+
+/// Interpret an OSC, setting `colors_changed` if any color was set or reset.
+fn dispatch_osc_internal<H: Handler>(
+    handler: &mut H,
+    params: &[&[u8]],
+    bell_terminated: bool,
+    preserve_title_whitespace: bool,
+    colors_changed: &mut bool,
+) {
+    if handler.osc_raw(params, bell_terminated) {
+        return;
+    }
+
+    let terminator = if bell_terminated { "\x07" } else { "\x1b\\" };
+
+    macro_rules! unhandled {
+        () => {{
+            let mut buf = String::new();
+            for items in params {
+                buf.push('[');
+                for item in *items {
+                    let _ = write!(buf, "{:?}", *item as char);
+                }
+                buf.push_str("],");
+            }
+            debug!("[unhandled osc_dispatch]: [{}] at line {}", &buf, line!());
+            handler.unhandled_osc(params, bell_terminated);
+        }};
+    }
+
+    if params.is_empty() || params[0].is_empty() {
+        return;
+    }
+
+    match params[0] {
+        // Set window title.
+        b"0" | b"2" => {
+            if params.len() >= 2 {
+                let title = (1..params.len())
+                    .filter_map(|i| params.str_at(i))
+                    .collect::<Vec<&str>>()
+                    .join(";");
+                let title = if preserve_title_whitespace { title } else { title.trim().to_owned() };
+                handler.set_title(Some(title));
+                return;
+            }
+            unhandled!();
+        },
+
+        // Set color index.
+        b"4" => {
+            if params.len() <= 1 || params.len() % 2 == 0 {
+                unhandled!();
+                return;
+            }
+
+            let mut failed = false;
+            for i in (1..params.len()).step_by(2) {
+                let index = match params.number_at(i).and_then(|index| u8::try_from(index).ok()) {
+                    Some(index) => index,
+                    None => {
+                        failed = true;
+                        continue;
+                    },
+                };
+
+                let spec = params[i + 1];
+                if let Some(c) = xparse_color(spec) {
+                    handler.set_color(index as usize, c);
+                    *colors_changed = true;
+                } else if spec == b"?" {
+                    let prefix = alloc::format!("4;{index}");
+                    handler.dynamic_color_sequence(prefix, index as usize, terminator);
+                } else {
+                    failed = true;
+                }
+            }
+
+            if failed {
+                unhandled!();
+            }
+        },
+
+        // Hyperlink.
+        b"8" if params.len() > 2 => {
+            // NOTE: The escape sequence is of form 'OSC 8 ; params ; URI ST', where
+            // URI is URL-encoded. However `;` is a special character and might be
+            // passed as is, thus we need to rebuild the URI.
+            let mut uri = params.str_at(2).unwrap_or_default().to_string();
+            for i in 3..params.len() {
+                uri.push(';');
+                uri.push_str(params.str_at(i).unwrap_or_default());
+            }
+
+            // The OSC 8 escape sequence must be stopped when getting an empty `uri`.
+            if uri.is_empty() {
+                handler.set_hyperlink(None);
+                return;
+            }
+
+            // Link parameters are in format of `key1=value1:key2=value2`. Currently only
+            // key `id` is defined.
+            let id = params
+                .str_at(1)
+                .and_then(|link_params| {
+                    link_params.split(':').find_map(|kv| kv.strip_prefix("id="))
+                })
+                .map(str::to_owned);
+
+            handler.set_hyperlink(Some(Hyperlink { id, uri }));
+        },
+
+        // Get/set dynamic colors.
+        b"10" | b"11" | b"12" | b"13" | b"14" | b"15" | b"16" | b"17" | b"18" | b"19" => {
+            if params.len() >= 2 {
+                if let Some(mut dynamic_code) =
+                    params.number_at(0).and_then(|code| u8::try_from(code).ok())
+                {
+                    let mut failed = false;
+                    for param in &params[1..] {
+                        // Subsequent parameters set the following dynamic colors.
+                        let index = match NamedColor::from_dynamic_color(dynamic_code) {
+                            Some(color) => color as usize,
+                            // End of setting dynamic colors.
+                            None => {
+                                failed = true;
+                                break;
+                            },
+                        };
+
+                        if let Some(color) = xparse_color(param) {
+                            handler.set_color(index, color);
+                            *colors_changed = true;
+                        } else if param == b"?" {
+                            handler.dynamic_color_sequence(
+                                dynamic_code.to_string(),
+                                index,
+                                terminator,
+                            );
+                        } else {
+                            failed = true;
+                        }
+                        dynamic_code += 1;
+                    }
+
+                    if failed {
+                        unhandled!();
+                    }
+                    return;
+                }
+            }
+            unhandled!();
+        },
+
+        // Set mouse cursor shape.
+        b"22" if params.len() == 2 => {
+            let shape = String::from_utf8_lossy(params[1]);
+            match CursorIcon::from_str(&shape) {
+                Ok(cursor_icon) => handler.set_mouse_cursor_icon(cursor_icon),
+                Err(_) => {
+                    debug!("[osc 22] unrecognized cursor icon shape: {shape:?}");
+                    handler.set_mouse_cursor_icon_raw(&shape);
+                },
+            }
+        },
+
+        // Set cursor style.
+        b"50" => {
+            if params.len() >= 2 && params[1].len() >= 13 && params[1][0..12] == *b"CursorShape=" {
+                let shape = match params[1][12] as char {
+                    '0' => CursorShape::Block,
+                    '1' => CursorShape::Beam,
+                    '2' => CursorShape::Underline,
+                    _ => return unhandled!(),
+                };
+                handler.set_cursor_shape(shape);
+                return;
+            }
+            unhandled!();
+        },
+
+        // Set clipboard.
+        b"52" => {
+            if params.len() < 3 {
+                return unhandled!();
+            }
+
+            let selectors = match params[1] {
+                [] => b"c",
+                selectors => selectors,
+            };
+
+            let valid = |selector| matches!(selector, b'c' | b'p' | b'q' | b's' | b'0'..=b'7');
+            if !selectors.iter().copied().all(valid) {
+                return unhandled!();
+            }
+
+            match params[2] {
+                b"?" => handler.clipboard_load_selectors(selectors, terminator),
+                base64 => handler.clipboard_store_selectors(selectors, base64),
+            }
+        },
+
+        // iTerm2 proprietary sequences.
+        b"1337" if params.len() >= 2 => match parse_iterm2_proprietary(&params[1..]) {
+            Some((keys, payload)) => handler.iterm2_proprietary(&keys, payload),
+            None => unhandled!(),
+        },
+
+        // Reset color index.
+        b"104" => {
+            // Reset all color indexes when no parameters are given.
+            if params.len() == 1 || params[1].is_empty() {
+                for i in 0..256 {
+                    handler.reset_color(i);
+                }
+                *colors_changed = true;
+                return;
+            }
+
+            // Reset color indexes given as parameters.
+            let mut failed = false;
+            for i in 1..params.len() {
+                match params.number_at(i).and_then(|index| u8::try_from(index).ok()) {
+                    Some(index) => {
+                        handler.reset_color(index as usize);
+                        *colors_changed = true;
+                    },
+                    None => failed = true,
+                }
+            }
+
+            if failed {
+                unhandled!();
+            }
+        },
+
+        // Reset dynamic colors.
+        b"110" | b"111" | b"112" | b"113" | b"114" | b"115" | b"116" | b"117" | b"118" | b"119" => {
+            let color = params
+                .number_at(0)
+                .and_then(|code| u8::try_from(code).ok()?.checked_sub(100))
+                .and_then(NamedColor::from_dynamic_color);
+            match color {
+                Some(color) => {
+                    handler.reset_color(color as usize);
+                    *colors_changed = true;
+                },
+                None => unhandled!(),
+            }
+        },
+
+        _ => unhandled!(),
+    }
+}
+
 #[inline]
 fn attrs_from_sgr_parameters<H: Handler>(handler: &mut H, params: &mut ParamsIter<'_>) {
     while let Some(param) = params.next() {
@@ -1889,6 +4140,8 @@ fn attrs_from_sgr_parameters<H: Handler>(handler: &mut H, params: &mut ParamsIte
             [58, params @ ..] => {
                 handle_colon_rgb(params).map(|color| Attr::UnderlineColor(Some(color)))
             },
+            [53] => Some(Attr::Overline),
+            [55] => Some(Attr::CancelOverline),
             [59] => Some(Attr::UnderlineColor(None)),
             [90] => Some(Attr::Foreground(Color::Named(NamedColor::BrightBlack))),
             [91] => Some(Attr::Foreground(Color::Named(NamedColor::BrightRed))),
@@ -1911,19 +4164,29 @@ fn attrs_from_sgr_parameters<H: Handler>(handler: &mut H, params: &mut ParamsIte
 
         match attr {
             Some(attr) => handler.terminal_attribute(attr),
-            None => continue,
+            None => handler.unhandled_sgr(param),
         }
     }
 }
 
-/// Handle colon separated rgb color escape sequence.
+/// Handle colon separated color escape sequence.
+///
+/// This accepts the indexed form `5:n`, the RGB form with a colorspace
+/// `2:colorspace:r:g:b` and the RGB form without colorspace `2:r:g:b`. The
+/// colorspace and any trailing subparameters are ignored.
 #[inline]
 fn handle_colon_rgb(params: &[u16]) -> Option<Color> {
-    let rgb_start = if params.len() > 4 { 2 } else { 1 };
-    let rgb_iter = params[rgb_start..].iter().copied();
-    let mut iter = iter::once(params[0]).chain(rgb_iter);
+    let rgb = |r: u16, g: u16, b: u16| {
+        let (r, g, b) = (u8::try_from(r).ok()?, u8::try_from(g).ok()?, u8::try_from(b).ok()?);
+        Some(Color::Spec(Rgb { r, g, b }))
+    };
 
-    parse_sgr_color(&mut iter)
+    match *params {
+        [5, index, ..] => Some(Color::Indexed(u8::try_from(index).ok()?)),
+        [2, r, g, b] => rgb(r, g, b),
+        [2, _colorspace, r, g, b, ..] => rgb(r, g, b),
+        _ => None,
+    }
 }
 
 /// Parse a color specifier from list of attributes.
@@ -2010,6 +4273,75 @@ pub mod C0 {
     pub const DEL: u8 = 0x7F;
 }
 
+/// C1 set of 8-bit control characters (from ECMA-48).
+#[allow(non_snake_case)]
+pub mod C1 {
+    /// Padding Character.
+    pub const PAD: u8 = 0x80;
+    /// High Octet Preset.
+    pub const HOP: u8 = 0x81;
+    /// Break Permitted Here.
+    pub const BPH: u8 = 0x82;
+    /// No Break Here.
+    pub const NBH: u8 = 0x83;
+    /// Index, move down one line keeping the same column.
+    pub const IND: u8 = 0x84;
+    /// Next Line, move to the first column of the next line.
+    pub const NEL: u8 = 0x85;
+    /// Start of Selected Area.
+    pub const SSA: u8 = 0x86;
+    /// End of Selected Area.
+    pub const ESA: u8 = 0x87;
+    /// Horizontal Tabulation Set, set a tab stop at the cursor column.
+    pub const HTS: u8 = 0x88;
+    /// Horizontal Tabulation with Justification.
+    pub const HTJ: u8 = 0x89;
+    /// Vertical Tabulation Set.
+    pub const VTS: u8 = 0x8A;
+    /// Partial Line Down.
+    pub const PLD: u8 = 0x8B;
+    /// Partial Line Up.
+    pub const PLU: u8 = 0x8C;
+    /// Reverse Index, move up one line keeping the same column.
+    pub const RI: u8 = 0x8D;
+    /// Single-Shift 2, use G2 for the next character only.
+    pub const SS2: u8 = 0x8E;
+    /// Single-Shift 3, use G3 for the next character only.
+    pub const SS3: u8 = 0x8F;
+    /// Device Control String, terminated by ST.
+    pub const DCS: u8 = 0x90;
+    /// Private Use 1.
+    pub const PU1: u8 = 0x91;
+    /// Private Use 2.
+    pub const PU2: u8 = 0x92;
+    /// Set Transmit State.
+    pub const STS: u8 = 0x93;
+    /// Cancel Character, delete the last character.
+    pub const CCH: u8 = 0x94;
+    /// Message Waiting.
+    pub const MW: u8 = 0x95;
+    /// Start of Protected Area.
+    pub const SPA: u8 = 0x96;
+    /// End of Protected Area.
+    pub const EPA: u8 = 0x97;
+    /// Start of String, terminated by ST.
+    pub const SOS: u8 = 0x98;
+    /// Single Graphic Character Introducer.
+    pub const SGCI: u8 = 0x99;
+    /// Single Character Introducer.
+    pub const SCI: u8 = 0x9A;
+    /// Control Sequence Introducer.
+    pub const CSI: u8 = 0x9B;
+    /// String Terminator.
+    pub const ST: u8 = 0x9C;
+    /// Operating System Command, terminated by ST.
+    pub const OSC: u8 = 0x9D;
+    /// Privacy Message, terminated by ST.
+    pub const PM: u8 = 0x9E;
+    /// Application Program Command, terminated by ST.
+    pub const APC: u8 = 0x9F;
+}
+
 // Tests for parsing escape sequences.
 //
 // Byte sequences used in these tests are recording of pty stdout.
@@ -2039,18 +4371,75 @@ mod tests {
         }
     }
 
+    /// Keys and payload of OSC 1337.
+    type Iterm2Sequence = (Vec<(String, String)>, Option<Vec<u8>>);
+
     struct MockHandler {
         index: CharsetIndex,
         charset: StandardCharset,
         attr: Option<Attr>,
+        attr_count: usize,
         identity_reported: bool,
         color: Option<Rgb>,
+        color_index: Option<usize>,
         reset_colors: Vec<usize>,
+        private_mode_reports: Vec<PrivateMode>,
+        private_device_status: Option<usize>,
+        cursor_style: Option<Option<CursorStyle>>,
+        cursor_shape: Option<CursorShape>,
+        unhandled_csi: Option<(char, Vec<Vec<u16>>, Vec<u8>)>,
+        unhandled_osc: Option<(Vec<Vec<u8>>, bool)>,
+        unhandled_esc: Option<(Vec<u8>, u8)>,
+        terminal_version_reported: bool,
+        tmux_passthrough: Option<Vec<u8>>,
+        warning_bell_volume: Option<u8>,
+        margin_bell_volume: Option<u8>,
+        repeated: Option<(usize, char)>,
+        scrolled_down: Vec<usize>,
+        saved_private_modes: Vec<PrivateMode>,
+        restored_private_modes: Vec<PrivateMode>,
+        private_modes: Vec<(PrivateMode, bool)>,
+        goto: Option<(i32, usize)>,
+        moved_up: usize,
+        input: Vec<char>,
+        color_queries: Vec<(String, usize, String)>,
+        unhandled_sgr: Vec<Vec<u16>>,
+        widths: Vec<(char, usize)>,
+        c1_transmission: Option<bool>,
+        conformance_level: Option<(u8, bool)>,
+        title_modes: TitleModes,
+        iterm2: Option<Iterm2Sequence>,
+        private_mode_states: Vec<(PrivateMode, ModeState)>,
+        soft_font: Option<(SoftFontHeader, Vec<u8>, Option<bool>)>,
+        sgr_stack: Vec<Option<SgrStackItems>>,
+        media_copy: Option<(MediaCopyMode, bool)>,
+        status_display: Option<StatusDisplay>,
+        scp: Option<(ScpCharPath, ScpUpdateMode)>,
+        suppress_osc: bool,
+        unknown_private_modes: Vec<Vec<u16>>,
+        sync_aborts: Vec<SyncAbortReason>,
+        raw_osc: Option<Vec<Vec<u8>>>,
+        status_display_type: Option<StatusDisplayType>,
+        attribute_change_extent: Option<AttributeChangeExtent>,
+        title_stack: Vec<(bool, TitleTarget)>,
+        mouse_cursor_icon: Option<CursorIcon>,
+        mouse_cursor_icon_raw: Option<String>,
+        decalns: usize,
+        clipboard_stores: Vec<(Vec<u8>, Vec<u8>)>,
+        clipboard_loads: Vec<Vec<u8>>,
+        title: Option<Option<String>>,
+        scrolling_region: Option<(usize, Option<usize>)>,
+        scrolling_region_resets: usize,
+        presentation_state: Option<(PresentationStateKind, Vec<u8>)>,
+        terminal_state: Option<(TerminalStateKind, Vec<u8>)>,
+        clear_modes: Vec<ClearMode>,
+        scrollback_clears: usize,
     }
 
     impl Handler for MockHandler {
         fn terminal_attribute(&mut self, attr: Attr) {
             self.attr = Some(attr);
+            self.attr_count += 1;
         }
 
         fn configure_charset(&mut self, index: CharsetIndex, charset: StandardCharset) {
@@ -2070,13 +4459,243 @@ mod tests {
             *self = Self::default();
         }
 
-        fn set_color(&mut self, _: usize, c: Rgb) {
+        fn set_color(&mut self, index: usize, c: Rgb) {
+            self.color_index = Some(index);
             self.color = Some(c);
         }
 
         fn reset_color(&mut self, index: usize) {
             self.reset_colors.push(index)
         }
+
+        fn set_private_mode(&mut self, mode: PrivateMode) {
+            self.private_modes.push((mode, true));
+        }
+
+        fn unset_private_mode(&mut self, mode: PrivateMode) {
+            self.private_modes.push((mode, false));
+        }
+
+        fn report_private_mode(&mut self, mode: PrivateMode) {
+            self.private_mode_reports.push(mode);
+        }
+
+        fn device_status_private(&mut self, status: usize) {
+            self.private_device_status = Some(status);
+        }
+
+        fn set_cursor_style(&mut self, style: Option<CursorStyle>) {
+            self.cursor_style = Some(style);
+        }
+
+        fn set_cursor_shape(&mut self, shape: CursorShape) {
+            self.cursor_shape = Some(shape);
+        }
+
+        fn unhandled_csi(&mut self, action: char, params: &Params, intermediates: &[u8]) {
+            let params = params.iter().map(|param| param.to_vec()).collect();
+            self.unhandled_csi = Some((action, params, intermediates.to_vec()));
+        }
+
+        fn sync_update_aborted(&mut self, reason: SyncAbortReason) {
+            self.sync_aborts.push(reason);
+        }
+
+        fn unknown_private_modes(&mut self, modes: &[u16]) {
+            self.unknown_private_modes.push(modes.to_vec());
+        }
+
+        fn osc_raw(&mut self, params: &[&[u8]], _bell_terminated: bool) -> bool {
+            self.raw_osc = Some(params.iter().map(|param| param.to_vec()).collect());
+            self.suppress_osc
+        }
+
+        fn unhandled_osc(&mut self, params: &[&[u8]], bell_terminated: bool) {
+            let params = params.iter().map(|param| param.to_vec()).collect();
+            self.unhandled_osc = Some((params, bell_terminated));
+        }
+
+        fn unhandled_esc(&mut self, intermediates: &[u8], byte: u8) {
+            self.unhandled_esc = Some((intermediates.to_vec(), byte));
+        }
+
+        fn report_terminal_version(&mut self) {
+            self.terminal_version_reported = true;
+        }
+
+        fn tmux_passthrough(&mut self, inner: &[u8]) {
+            self.tmux_passthrough = Some(inner.to_vec());
+        }
+
+        fn set_warning_bell_volume(&mut self, level: u8) {
+            self.warning_bell_volume = Some(level);
+        }
+
+        fn set_margin_bell_volume(&mut self, level: u8) {
+            self.margin_bell_volume = Some(level);
+        }
+
+        fn repeat_preceding(&mut self, count: usize, c: char) {
+            self.repeated = Some((count, c));
+        }
+
+        fn scroll_down(&mut self, rows: usize) {
+            self.scrolled_down.push(rows);
+        }
+
+        fn save_private_modes(&mut self, modes: &[PrivateMode]) {
+            self.saved_private_modes = modes.to_vec();
+        }
+
+        fn restore_private_modes(&mut self, modes: &[PrivateMode]) {
+            self.restored_private_modes = modes.to_vec();
+        }
+
+        fn goto(&mut self, line: i32, col: usize) {
+            self.goto = Some((line, col));
+        }
+
+        fn move_up(&mut self, rows: usize) {
+            self.moved_up += rows;
+        }
+
+        fn input(&mut self, c: char) {
+            self.input.push(c);
+        }
+
+        fn unhandled_sgr(&mut self, param: &[u16]) {
+            self.unhandled_sgr.push(param.to_vec());
+        }
+
+        fn input_with_width(&mut self, c: char, width: usize) {
+            self.widths.push((c, width));
+        }
+
+        fn set_c1_transmission(&mut self, eight_bit: bool) {
+            self.c1_transmission = Some(eight_bit);
+        }
+
+        fn iterm2_proprietary(&mut self, keys: &[(&str, &str)], payload: Option<&[u8]>) {
+            let keys = keys.iter().map(|(key, value)| (key.to_string(), value.to_string()));
+            self.iterm2 = Some((keys.collect(), payload.map(|payload| payload.to_vec())));
+        }
+
+        fn set_title_modes(&mut self, modes: TitleModes) {
+            self.title_modes |= modes;
+        }
+
+        fn reset_title_modes(&mut self, modes: TitleModes) {
+            self.title_modes -= modes;
+        }
+
+        fn set_conformance_level(&mut self, level: u8, eight_bit: bool) {
+            self.conformance_level = Some((level, eight_bit));
+        }
+
+        fn report_private_mode_state(&mut self, mode: PrivateMode, state: ModeState) {
+            self.private_mode_states.push((mode, state));
+        }
+
+        fn set_title(&mut self, title: Option<String>) {
+            self.title = Some(title);
+        }
+
+        fn set_scrolling_region(&mut self, top: usize, bottom: Option<usize>) {
+            self.scrolling_region = Some((top, bottom));
+        }
+
+        fn reset_scrolling_region(&mut self) {
+            self.scrolling_region_resets += 1;
+            self.set_scrolling_region(1, None);
+        }
+
+        fn media_copy(&mut self, mode: MediaCopyMode, private: bool) {
+            self.media_copy = Some((mode, private));
+        }
+
+        fn set_scp(&mut self, char_path: ScpCharPath, update_mode: ScpUpdateMode) {
+            self.scp = Some((char_path, update_mode));
+        }
+
+        fn select_status_display(&mut self, display: StatusDisplay) {
+            self.status_display = Some(display);
+        }
+
+        fn set_status_display_type(&mut self, kind: StatusDisplayType) {
+            self.status_display_type = Some(kind);
+        }
+
+        fn select_attribute_change_extent(&mut self, extent: AttributeChangeExtent) {
+            self.attribute_change_extent = Some(extent);
+        }
+
+        fn set_mouse_cursor_icon(&mut self, icon: CursorIcon) {
+            self.mouse_cursor_icon = Some(icon);
+        }
+
+        fn set_mouse_cursor_icon_raw(&mut self, name: &str) {
+            self.mouse_cursor_icon_raw = Some(name.into());
+        }
+
+        fn decaln(&mut self) {
+            self.decalns += 1;
+        }
+
+        fn push_title(&mut self, target: TitleTarget) {
+            self.title_stack.push((true, target));
+        }
+
+        fn pop_title(&mut self, target: TitleTarget) {
+            self.title_stack.push((false, target));
+        }
+
+        fn clipboard_store_selectors(&mut self, selectors: &[u8], data: &[u8]) {
+            self.clipboard_stores.push((selectors.to_vec(), data.to_vec()));
+        }
+
+        fn clipboard_load_selectors(&mut self, selectors: &[u8], _terminator: &str) {
+            self.clipboard_loads.push(selectors.to_vec());
+        }
+
+        fn push_sgr(&mut self, items: Option<SgrStackItems>) {
+            self.sgr_stack.push(items);
+        }
+
+        fn pop_sgr(&mut self) {
+            self.sgr_stack.pop();
+        }
+
+        fn restore_presentation_state(&mut self, kind: PresentationStateKind, data: &[u8]) {
+            self.presentation_state = Some((kind, data.to_vec()));
+        }
+
+        fn restore_terminal_state(&mut self, kind: TerminalStateKind, data: &[u8]) {
+            self.terminal_state = Some((kind, data.to_vec()));
+        }
+
+        fn clear_screen(&mut self, mode: ClearMode) {
+            self.clear_modes.push(mode);
+        }
+
+        fn clear_scrollback(&mut self) {
+            self.scrollback_clears += 1;
+        }
+
+        fn define_soft_font(&mut self, header: SoftFontHeader) {
+            self.soft_font = Some((header, Vec::new(), None));
+        }
+
+        fn soft_font_put(&mut self, byte: u8) {
+            self.soft_font.as_mut().unwrap().1.push(byte);
+        }
+
+        fn soft_font_end(&mut self, aborted: bool) {
+            self.soft_font.as_mut().unwrap().2 = Some(aborted);
+        }
+
+        fn dynamic_color_sequence(&mut self, prefix: String, index: usize, terminator: &str) {
+            self.color_queries.push((prefix, index, terminator.into()));
+        }
     }
 
     impl Default for MockHandler {
@@ -2085,9 +4704,62 @@ mod tests {
                 index: CharsetIndex::G0,
                 charset: StandardCharset::Ascii,
                 attr: None,
+                attr_count: 0,
                 identity_reported: false,
                 color: None,
+                color_index: None,
                 reset_colors: Vec::new(),
+                private_mode_reports: Vec::new(),
+                private_device_status: None,
+                cursor_style: None,
+                cursor_shape: None,
+                unhandled_csi: None,
+                unhandled_osc: None,
+                unhandled_esc: None,
+                terminal_version_reported: false,
+                tmux_passthrough: None,
+                warning_bell_volume: None,
+                margin_bell_volume: None,
+                repeated: None,
+                scrolled_down: Vec::new(),
+                saved_private_modes: Vec::new(),
+                restored_private_modes: Vec::new(),
+                private_modes: Vec::new(),
+                goto: None,
+                moved_up: 0,
+                input: Vec::new(),
+                color_queries: Vec::new(),
+                unhandled_sgr: Vec::new(),
+                widths: Vec::new(),
+                c1_transmission: None,
+                conformance_level: None,
+                title_modes: TitleModes::empty(),
+                iterm2: None,
+                private_mode_states: Vec::new(),
+                soft_font: None,
+                sgr_stack: Vec::new(),
+                media_copy: None,
+                status_display: None,
+                scp: None,
+                suppress_osc: false,
+                unknown_private_modes: Vec::new(),
+                sync_aborts: Vec::new(),
+                raw_osc: None,
+                status_display_type: None,
+                attribute_change_extent: None,
+                title_stack: Vec::new(),
+                mouse_cursor_icon: None,
+                mouse_cursor_icon_raw: None,
+                decalns: 0,
+                clipboard_stores: Vec::new(),
+                clipboard_loads: Vec::new(),
+                title: None,
+                scrolling_region: None,
+                scrolling_region_resets: 0,
+                presentation_state: None,
+                terminal_state: None,
+                clear_modes: Vec::new(),
+                scrollback_clears: 0,
             }
         }
     }
@@ -2170,10 +4842,49 @@ mod tests {
         assert_eq!(handler.attr, Some(Attr::Foreground(Color::Spec(spec))));
     }
 
-    /// No exactly a test; useful for debugging.
     #[test]
-    fn parse_zsh_startup() {
-        static BYTES: &[u8] = &[
+    fn parse_colon_sgr_colors() {
+        let mut parser = Processor::<TestSyncHandler>::new();
+        let mut handler = MockHandler::default();
+
+        let spec = Color::Spec(Rgb { r: 1, g: 2, b: 3 });
+
+        parser.advance(&mut handler, b"\x1b[38:2:0:1:2:3m");
+        assert_eq!(handler.attr, Some(Attr::Foreground(spec)));
+
+        parser.advance(&mut handler, b"\x1b[48:2:1:2:3m");
+        assert_eq!(handler.attr, Some(Attr::Background(spec)));
+
+        parser.advance(&mut handler, b"\x1b[58:2::1:2:3m");
+        assert_eq!(handler.attr, Some(Attr::UnderlineColor(Some(spec))));
+
+        parser.advance(&mut handler, b"\x1b[38:2:0:1:2:3:0:0m");
+        assert_eq!(handler.attr, Some(Attr::Foreground(spec)));
+
+        parser.advance(&mut handler, b"\x1b[38:5:9m");
+        assert_eq!(handler.attr, Some(Attr::Foreground(Color::Indexed(9))));
+
+        parser.advance(&mut handler, b"\x1b[58:5:1:2:3:4m");
+        assert_eq!(handler.attr, Some(Attr::UnderlineColor(Some(Color::Indexed(1)))));
+    }
+
+    #[test]
+    fn parse_invalid_colon_sgr_colors() {
+        let mut parser = Processor::<TestSyncHandler>::new();
+        let mut handler = MockHandler::default();
+
+        for sgr in [&b"\x1b[38:2:1:2m"[..], b"\x1b[38:2:0:1:256:3m", b"\x1b[48:5m", b"\x1b[58:3:1m"]
+        {
+            handler.attr = None;
+            parser.advance(&mut handler, sgr);
+            assert_eq!(handler.attr, None);
+        }
+    }
+
+    /// No exactly a test; useful for debugging.
+    #[test]
+    fn parse_zsh_startup() {
+        static BYTES: &[u8] = &[
             0x1B, b'[', b'1', b'm', 0x1B, b'[', b'7', b'm', b'%', 0x1B, b'[', b'2', b'7', b'm',
             0x1B, b'[', b'1', b'm', 0x1B, b'[', b'0', b'm', b' ', b' ', b' ', b' ', b' ', b' ',
             b' ', b' ', b' ', b' ', b' ', b' ', b' ', b' ', b' ', b' ', b' ', b' ', b' ', b' ',
@@ -2207,6 +4918,19 @@ mod tests {
         assert_eq!(handler.charset, StandardCharset::SpecialCharacterAndLineDrawing);
     }
 
+    #[test]
+    fn parse_designate_uk_charset() {
+        let mut parser = Processor::<TestSyncHandler>::new();
+        let mut handler = MockHandler::default();
+
+        parser.advance(&mut handler, b"\x1b)A");
+
+        assert_eq!(handler.index, CharsetIndex::G1);
+        assert_eq!(handler.charset, StandardCharset::Uk);
+        assert_eq!(handler.charset.map('#'), '£');
+        assert_eq!(handler.charset.map('$'), '$');
+    }
+
     #[test]
     fn parse_designate_g1_as_line_drawing_and_invoke() {
         static BYTES: &[u8] = &[0x1B, b')', b'0', 0x0E];
@@ -2252,19 +4976,94 @@ mod tests {
         assert_eq!(xparse_color(b"#f"), None);
     }
 
+    #[test]
+    fn rgb_blend() {
+        let from = Rgb { r: 0, g: 100, b: 255 };
+        let to = Rgb { r: 255, g: 200, b: 0 };
+
+        assert_eq!(from.blend(to, 0.), from);
+        assert_eq!(from.blend(to, 0.5), Rgb { r: 128, g: 150, b: 128 });
+        assert_eq!(from.blend(to, 1.), to);
+        assert_eq!(from.blend(to, -1.), from);
+        assert_eq!(from.blend(to, 2.), to);
+        assert_eq!(from.mix(to), from.blend(to, 0.5));
+    }
+
+    #[cfg(any(feature = "std", feature = "libm"))]
+    #[test]
+    fn black_white_contrast() {
+        let black = Rgb { r: 0, g: 0, b: 0 };
+        let white = Rgb { r: 255, g: 255, b: 255 };
+
+        assert!((black.contrast(white) - 21.).abs() < 1e-9);
+        assert_eq!(black.contrast(white), white.contrast(black));
+    }
+
     #[test]
     fn parse_invalid_number() {
-        assert_eq!(parse_number(b"1abc"), None);
+        assert_eq!(parse_number::<u8>(b"1abc"), None);
     }
 
     #[test]
     fn parse_valid_number() {
-        assert_eq!(parse_number(b"123"), Some(123));
+        assert_eq!(parse_number::<u8>(b"123"), Some(123));
+    }
+
+    #[test]
+    fn parse_color_spellings() {
+        let orange = Some(Color::Spec(Rgb { r: 255, g: 128, b: 0 }));
+        assert_eq!(Color::parse(b"rgb:ff/80/00"), orange);
+        assert_eq!(Color::parse(b"rgb:ffff/8080/0000"), orange);
+        assert_eq!(Color::parse(b"#ff8000"), orange);
+        assert_eq!(Color::parse(b"#f80"), Some(Color::Spec(Rgb { r: 240, g: 128, b: 0 })));
+        assert_eq!(Color::parse(b"5"), Some(Color::Indexed(5)));
+        assert_eq!(Color::parse(b"255"), Some(Color::Indexed(255)));
+    }
+
+    #[test]
+    fn parse_invalid_color() {
+        assert_eq!(Color::parse(b""), None);
+        assert_eq!(Color::parse(b"256"), None);
+        assert_eq!(Color::parse(b"5x"), None);
+        assert_eq!(Color::parse(b"#"), None);
+        assert_eq!(Color::parse(b"rgb:ff/80"), None);
+        assert_eq!(Color::parse(b"orange"), None);
+    }
+
+    #[test]
+    fn parse_decrpss_sgr() {
+        let report = parse_decrpss(b"\x1bP1$r0m\x1b\\");
+        assert_eq!(report, Some(DecrpssReport { valid: true, setting: b"0m" }));
+
+        let reply = format_decrqss_sgr(&[Attr::Bold, Attr::Italic]);
+        let report = parse_decrpss(reply.as_bytes());
+        assert_eq!(report, Some(DecrpssReport { valid: true, setting: b"1;3m" }));
+
+        let report = parse_decrpss(b"\x900$r\x9c");
+        assert_eq!(report, Some(DecrpssReport { valid: false, setting: b"" }));
+    }
+
+    #[test]
+    fn parse_invalid_decrpss() {
+        assert_eq!(parse_decrpss(b""), None);
+        assert_eq!(parse_decrpss(b"\x1bP1$r0m"), None);
+        assert_eq!(parse_decrpss(b"1$r0m\x1b\\"), None);
+        assert_eq!(parse_decrpss(b"\x1bP2$r0m\x1b\\"), None);
+        assert_eq!(parse_decrpss(b"\x1bP>|xterm\x1b\\"), None);
     }
 
     #[test]
     fn parse_number_too_large() {
-        assert_eq!(parse_number(b"321"), None);
+        assert_eq!(parse_number::<u8>(b"321"), None);
+    }
+
+    #[test]
+    fn parse_number_bounds() {
+        assert_eq!(parse_number::<u8>(b"255"), Some(255));
+        assert_eq!(parse_number::<u8>(b"256"), None);
+        assert_eq!(parse_number::<u16>(b"1000"), Some(1000));
+        assert_eq!(parse_number::<u16>(b"65536"), None);
+        assert_eq!(parse_number::<u32>(b"99999999999"), None);
     }
 
     #[test]
@@ -2279,6 +5078,34 @@ mod tests {
         assert_eq!(handler.color, Some(Rgb { r: 0xF0, g: 0xF0, b: 0xF0 }));
     }
 
+    #[test]
+    fn parse_osc4_multi_query() {
+        let mut parser = Processor::<TestSyncHandler>::new();
+        let mut handler = MockHandler::default();
+
+        parser.advance(&mut handler, b"\x1b]4;1;?;2;?\x07");
+
+        assert_eq!(handler.color_queries, vec![
+            (String::from("4;1"), 1, String::from("\x07")),
+            (String::from("4;2"), 2, String::from("\x07")),
+        ]);
+    }
+
+    #[test]
+    fn parse_osc4_mixed_set_and_query() {
+        let mut parser = Processor::<TestSyncHandler>::new();
+        let mut handler = MockHandler::default();
+
+        parser.advance(&mut handler, b"\x1b]4;1;?;3;#ff0000;5;?\x1b\\");
+
+        assert_eq!(handler.color_index, Some(3));
+        assert_eq!(handler.color, Some(Rgb { r: 0xFF, g: 0, b: 0 }));
+        assert_eq!(handler.color_queries, vec![
+            (String::from("4;1"), 1, String::from("\x1b\\")),
+            (String::from("4;5"), 5, String::from("\x1b\\")),
+        ]);
+    }
+
     #[test]
     fn parse_osc104_reset_color() {
         let bytes: &[u8] = b"\x1b]104;1;\x1b\\";
@@ -2317,6 +5144,1779 @@ mod tests {
         assert_eq!(handler.reset_colors, expected);
     }
 
+    #[test]
+    fn parse_decrqm_multiple_private_modes() {
+        let bytes: &[u8] = b"\x1b[?1;25$p";
+
+        let mut parser = Processor::<TestSyncHandler>::new();
+        let mut handler = MockHandler::default();
+
+        parser.advance(&mut handler, bytes);
+
+        assert_eq!(handler.private_mode_reports, vec![
+            PrivateMode::Named(NamedPrivateMode::CursorKeys),
+            PrivateMode::Named(NamedPrivateMode::ShowCursor),
+        ]);
+    }
+
+    #[test]
+    fn parse_private_device_status() {
+        let mut parser = Processor::<TestSyncHandler>::new();
+        let mut handler = MockHandler::default();
+
+        parser.advance(&mut handler, b"\x1b[?15n");
+        assert_eq!(handler.private_device_status.take(), Some(15));
+
+        parser.advance(&mut handler, b"\x1b[?6n");
+        assert_eq!(handler.private_device_status.take(), Some(6));
+
+        // Public DSR must not be routed to the private handler.
+        parser.advance(&mut handler, b"\x1b[6n");
+        assert_eq!(handler.private_device_status, None);
+    }
+
+    #[test]
+    fn encode_decscusr() {
+        let style = |shape, blinking| CursorStyle { shape, blinking };
+
+        assert_eq!(encode_cursor_style(style(CursorShape::Block, true)), "\x1b[1 q");
+        assert_eq!(encode_cursor_style(style(CursorShape::Block, false)), "\x1b[2 q");
+        assert_eq!(encode_cursor_style(style(CursorShape::Underline, true)), "\x1b[3 q");
+        assert_eq!(encode_cursor_style(style(CursorShape::Underline, false)), "\x1b[4 q");
+        assert_eq!(encode_cursor_style(style(CursorShape::Beam, true)), "\x1b[5 q");
+        assert_eq!(encode_cursor_style(style(CursorShape::Beam, false)), "\x1b[6 q");
+        assert_eq!(encode_cursor_style(style(CursorShape::HollowBlock, false)), "\x1b[2 q");
+        assert_eq!(encode_cursor_style(style(CursorShape::Hidden, true)), "\x1b[0 q");
+    }
+
+    #[test]
+    fn decscusr_round_trip() {
+        let mut parser = Processor::<TestSyncHandler>::new();
+        let mut handler = MockHandler::default();
+
+        for shape in [CursorShape::Block, CursorShape::Underline, CursorShape::Beam] {
+            for blinking in [true, false] {
+                let style = CursorStyle { shape, blinking };
+                parser.advance(&mut handler, encode_cursor_style(style).as_bytes());
+                assert_eq!(handler.cursor_style.take(), Some(Some(style)));
+            }
+        }
+    }
+
+    #[test]
+    fn osc50_round_trip() {
+        let mut parser = Processor::<TestSyncHandler>::new();
+        let mut handler = MockHandler::default();
+
+        assert_eq!(encode_cursor_shape_osc50(CursorShape::Beam), "\x1b]50;CursorShape=1\x1b\\");
+
+        for shape in [CursorShape::Block, CursorShape::Underline, CursorShape::Beam] {
+            parser.advance(&mut handler, encode_cursor_shape_osc50(shape).as_bytes());
+            assert_eq!(handler.cursor_shape.take(), Some(shape));
+        }
+    }
+
+    #[test]
+    fn parse_unhandled_csi() {
+        let mut parser = Processor::<TestSyncHandler>::new();
+        let mut handler = MockHandler::default();
+
+        // DECAC - Assign Color.
+        parser.advance(&mut handler, b"\x1b[1;2;3,|");
+
+        let params = vec![vec![1], vec![2], vec![3]];
+        assert_eq!(handler.unhandled_csi, Some(('|', params, vec![b','])));
+    }
+
+    #[test]
+    fn parse_unhandled_csi_final() {
+        let mut parser = Processor::<TestSyncHandler>::new();
+        let mut handler = MockHandler::default();
+
+        parser.advance(&mut handler, b"\x1b[?7y");
+
+        assert_eq!(handler.unhandled_csi, Some(('y', vec![vec![7]], vec![b'?'])));
+    }
+
+    #[test]
+    fn parse_unhandled_space_intermediate() {
+        let mut parser = Processor::<TestSyncHandler>::new();
+        let mut handler = MockHandler::default();
+
+        parser.advance(&mut handler, b"\x1b[1 Z");
+        assert_eq!(handler.unhandled_csi, Some(('Z', vec![vec![1]], vec![b' '])));
+
+        // SL and SR.
+        parser.advance(&mut handler, b"\x1b[2 @");
+        assert_eq!(handler.unhandled_csi, Some(('@', vec![vec![2]], vec![b' '])));
+        parser.advance(&mut handler, b"\x1b[3 A");
+        assert_eq!(handler.unhandled_csi, Some(('A', vec![vec![3]], vec![b' '])));
+
+        parser.advance(&mut handler, b"\x1b[ c");
+        assert_eq!(handler.unhandled_csi, Some(('c', vec![vec![0]], vec![b' '])));
+        assert!(!handler.identity_reported);
+    }
+
+    #[test]
+    fn parse_clear_scrollback() {
+        let mut parser = Processor::<TestSyncHandler>::new();
+        let mut handler = MockHandler::default();
+
+        parser.advance(&mut handler, b"\x1b[3J\x1b[J\x1b[2J\x1b[1J");
+
+        assert_eq!(handler.scrollback_clears, 1);
+        assert_eq!(handler.clear_modes, vec![ClearMode::Below, ClearMode::All, ClearMode::Above]);
+    }
+
+    #[test]
+    fn clear_scrollback_default() {
+        #[derive(Default)]
+        struct ClearHandler(Vec<ClearMode>);
+
+        impl Handler for ClearHandler {
+            fn clear_screen(&mut self, mode: ClearMode) {
+                self.0.push(mode);
+            }
+        }
+
+        let mut parser = Processor::<TestSyncHandler>::new();
+        let mut handler = ClearHandler::default();
+
+        parser.advance(&mut handler, b"\x1b[3J\x1b[2J");
+
+        assert_eq!(handler.0, vec![ClearMode::Saved, ClearMode::All]);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn advance_vectored() {
+        let mut parser = Processor::<TestSyncHandler>::new();
+        let mut handler = MockHandler::default();
+
+        let bufs = [IoSlice::new(b"a\x1b[1"), IoSlice::new(b""), IoSlice::new(b";2Hb\xe2\x96")];
+        parser.advance_vectored(&mut handler, &bufs);
+        parser.advance_vectored(&mut handler, &[IoSlice::new(b"\x88")]);
+
+        assert_eq!(handler.goto, Some((0, 1)));
+        assert_eq!(handler.input, vec!['a', 'b', '█']);
+    }
+
+    #[test]
+    fn parse_after_unhandled_space_intermediate() {
+        let mut parser = Processor::<TestSyncHandler>::new();
+        let mut handler = MockHandler::default();
+
+        // Unknown space intermediate finals must not be handled like their plain
+        // counterparts.
+        parser.advance(&mut handler, b"\x1b[1 Da\x1b[2A");
+        assert_eq!(handler.unhandled_csi, Some(('D', vec![vec![1]], vec![b' '])));
+        assert_eq!(handler.input, vec!['a']);
+        assert_eq!(handler.moved_up, 2);
+
+        // Sequences split across reads are consumed the same way.
+        let mut handler = MockHandler::default();
+        parser.advance(&mut handler, b"\x1b[1 ");
+        parser.advance(&mut handler, b"Db\x1b[A");
+        assert_eq!(handler.unhandled_csi, Some(('D', vec![vec![1]], vec![b' '])));
+        assert_eq!(handler.input, vec!['b']);
+        assert_eq!(handler.moved_up, 1);
+    }
+
+    #[test]
+    fn parse_osc_color_index_out_of_range() {
+        let mut parser = Processor::<TestSyncHandler>::new();
+        let mut handler = MockHandler::default();
+
+        parser.advance(&mut handler, b"\x1b]4;266;#ffffff\x07");
+        assert_eq!(handler.color_index, None);
+        let params = vec![b"4".to_vec(), b"266".to_vec(), b"#ffffff".to_vec()];
+        assert_eq!(handler.unhandled_osc, Some((params, true)));
+
+        parser.advance(&mut handler, b"\x1b]104;266;1\x07");
+        assert_eq!(handler.reset_colors, vec![1]);
+        let params = vec![b"104".to_vec(), b"266".to_vec(), b"1".to_vec()];
+        assert_eq!(handler.unhandled_osc, Some((params, true)));
+    }
+
+    #[test]
+    fn parse_partially_unhandled_osc() {
+        #[derive(Default)]
+        struct OscHandler {
+            colors: Vec<usize>,
+            unhandled: usize,
+        }
+
+        impl Handler for OscHandler {
+            fn set_color(&mut self, index: usize, _color: Rgb) {
+                self.colors.push(index);
+            }
+
+            fn reset_color(&mut self, index: usize) {
+                self.colors.push(index);
+            }
+
+            fn unhandled_osc(&mut self, _params: &[&[u8]], _bell_terminated: bool) {
+                self.unhandled += 1;
+            }
+        }
+
+        let mut parser = Processor::<TestSyncHandler>::new();
+        let mut handler = OscHandler::default();
+
+        parser.advance(&mut handler, b"\x1b]4;x;#ffffff;1;#ffffff;2;invalid;3;#000000\x07");
+        assert_eq!(handler.colors, vec![1, 3]);
+        assert_eq!(handler.unhandled, 1);
+
+        handler = OscHandler::default();
+        parser.advance(&mut handler, b"\x1b]104;x;1;y;2\x07");
+        assert_eq!(handler.colors, vec![1, 2]);
+        assert_eq!(handler.unhandled, 1);
+
+        handler = OscHandler::default();
+        parser.advance(&mut handler, b"\x1b]18;invalid;#ffffff;#ffffff;#ffffff\x07");
+        assert_eq!(handler.colors, vec![NamedColor::HighlightForeground as usize]);
+        assert_eq!(handler.unhandled, 1);
+    }
+
+    #[test]
+    fn dispatch_osc_title() {
+        #[derive(Default)]
+        struct OscPerformer(MockHandler);
+
+        impl crate::Perform for OscPerformer {
+            fn osc_dispatch(&mut self, params: &[&[u8]], bell_terminated: bool) {
+                dispatch_osc(&mut self.0, params, bell_terminated);
+            }
+        }
+
+        let mut handler = MockHandler::default();
+        dispatch_osc(&mut handler, &[b"2", b" vim ", b"file"], true);
+        assert_eq!(handler.title, Some(Some("vim ;file".into())));
+
+        let mut performer = OscPerformer::default();
+        crate::Parser::new().advance(&mut performer, b"\x1b]2;title\x1b\\\x1b]777;x\x07");
+        assert_eq!(performer.0.title, Some(Some("title".into())));
+        assert_eq!(performer.0.unhandled_osc, Some((vec![b"777".to_vec(), b"x".to_vec()], true)));
+    }
+
+    #[test]
+    fn parse_unhandled_osc() {
+        let mut parser = Processor::<TestSyncHandler>::new();
+        let mut handler = MockHandler::default();
+
+        parser.advance(&mut handler, b"\x1b]777;notify;title\x07");
+
+        let params = vec![b"777".to_vec(), b"notify".to_vec(), b"title".to_vec()];
+        assert_eq!(handler.unhandled_osc, Some((params, true)));
+    }
+
+    #[test]
+    fn parse_unhandled_esc() {
+        let mut parser = Processor::<TestSyncHandler>::new();
+        let mut handler = MockHandler::default();
+
+        parser.advance(&mut handler, b"\x1b%G");
+
+        assert_eq!(handler.unhandled_esc, Some((vec![b'%'], b'G')));
+    }
+
+    #[test]
+    fn parse_xtversion() {
+        let mut parser = Processor::<TestSyncHandler>::new();
+        let mut handler = MockHandler::default();
+
+        parser.advance(&mut handler, b"\x1b[>q");
+        assert!(handler.terminal_version_reported);
+
+        let mut handler = MockHandler::default();
+        parser.advance(&mut handler, b"\x1b[>0q");
+        assert!(handler.terminal_version_reported);
+
+        let mut handler = MockHandler::default();
+        parser.advance(&mut handler, b"\x1b[>1q");
+        assert!(!handler.terminal_version_reported);
+    }
+
+    #[test]
+    fn xtversion_reply() {
+        assert_eq!(format_xtversion("alacritty", "0.13.0"), "\x1bP>|alacritty 0.13.0\x1b\\");
+    }
+
+    #[test]
+    fn text_area_size_replies() {
+        assert_eq!(format_text_area_pixels(600, 800), "\x1b[4;600;800t");
+        assert_eq!(format_text_area_chars(24, 80), "\x1b[8;24;80t");
+    }
+
+    #[test]
+    fn decrqss_sgr_reply() {
+        let attrs = [Attr::Bold, Attr::Foreground(Color::Named(NamedColor::Red))];
+        assert_eq!(format_decrqss_sgr(&attrs), "\x1bP1$r1;31m\x1b\\");
+
+        let attrs = [
+            Attr::Reset,
+            Attr::Undercurl,
+            Attr::Background(Color::Named(NamedColor::BrightBlue)),
+            Attr::Foreground(Color::Named(NamedColor::Cursor)),
+            Attr::Foreground(Color::Spec(Rgb { r: 1, g: 2, b: 3 })),
+            Attr::UnderlineColor(Some(Color::Named(NamedColor::Green))),
+        ];
+        assert_eq!(format_decrqss_sgr(&attrs), "\x1bP1$r0;4:3;104;38;2;1;2;3;58;5;2m\x1b\\");
+
+        assert_eq!(format_decrqss_sgr(&[]), "\x1bP1$r0m\x1b\\");
+    }
+
+    #[test]
+    fn decrqss_decstbm_reply() {
+        assert_eq!(format_decrqss_decstbm(1, 24), "\x1bP1$r1;24r\x1b\\");
+    }
+
+    #[test]
+    fn decrqss_decscusr_reply() {
+        let style = CursorStyle { shape: CursorShape::Beam, blinking: false };
+        assert_eq!(format_decrqss_decscusr(style), "\x1bP1$r6 q\x1b\\");
+    }
+
+    #[test]
+    fn parse_tmux_passthrough() {
+        let mut parser = Processor::<TestSyncHandler>::new();
+        let mut handler = MockHandler::default();
+
+        parser.advance(&mut handler, b"\x1bPtmux;\x1b\x1b[1m\x1b\\");
+
+        let inner = handler.tmux_passthrough.take().unwrap();
+        assert_eq!(inner, b"\x1b[1m");
+        assert_eq!(handler.attr, None);
+
+        // Re-feed the unwrapped sequence.
+        parser.advance(&mut handler, &inner);
+        assert_eq!(handler.attr, Some(Attr::Bold));
+    }
+
+    #[test]
+    fn parse_cancelled_tmux_passthrough() {
+        let mut parser = Processor::<TestSyncHandler>::new();
+        let mut handler = MockHandler::default();
+
+        parser.advance(&mut handler, b"\x1bPtmux;\x1b\x1b[1m\x18");
+
+        assert_eq!(handler.tmux_passthrough, None);
+    }
+
+    #[test]
+    fn parse_split_tmux_passthrough() {
+        let mut parser = Processor::<TestSyncHandler>::new();
+        let mut handler = MockHandler::default();
+
+        for byte in b"\x1bPtmux;\x1b\x1b]2;\xc3\xa9\x07\x1b\\" {
+            parser.advance(&mut handler, &[*byte]);
+        }
+
+        assert_eq!(handler.tmux_passthrough, Some("\x1b]2;é\x07".as_bytes().to_vec()));
+        assert_eq!(handler.title, None);
+    }
+
+    #[test]
+    fn parse_unterminated_tmux_passthrough() {
+        let mut parser = Processor::<TestSyncHandler>::new();
+        let mut handler = MockHandler::default();
+
+        // Escapes which are not doubled terminate the DCS.
+        parser.advance(&mut handler, b"\x1bPtmux;a\x1b[1m");
+
+        assert_eq!(handler.tmux_passthrough, Some(b"a".to_vec()));
+        assert_eq!(handler.attr, Some(Attr::Bold));
+    }
+
+    #[test]
+    fn parse_oversized_dcs() {
+        let mut parser = Processor::<TestSyncHandler>::new();
+        let mut handler = MockHandler::default();
+
+        let mut bytes = b"\x1bPtmux;".to_vec();
+        bytes.resize(bytes.len() + DCS_BUFFER_SIZE + 1, b'a');
+        bytes.extend_from_slice(b"\x1b\x1b[1m\x1b\\");
+        parser.advance(&mut handler, &bytes);
+
+        assert_eq!(handler.tmux_passthrough, None);
+        assert_eq!(handler.attr, None);
+
+        let mut bytes = b"\x1bP1$t".to_vec();
+        bytes.resize(bytes.len() + DCS_BUFFER_SIZE + 1, b'1');
+        bytes.extend_from_slice(b"\x1b\\");
+        parser.advance(&mut handler, &bytes);
+
+        assert_eq!(handler.presentation_state, None);
+    }
+
+    #[test]
+    fn parse_restore_presentation_state() {
+        let mut parser = Processor::<TestSyncHandler>::new();
+        let mut handler = MockHandler::default();
+
+        parser.advance(&mut handler, b"\x1bP2$t9/17/25\x1b\\");
+        assert_eq!(
+            handler.presentation_state,
+            Some((PresentationStateKind::TabStops, b"9/17/25".to_vec()))
+        );
+
+        parser.advance(&mut handler, b"\x1bP1$t1;1;1;@;@;@;0;2;@;BBBB\x1b\\");
+        let data = b"1;1;1;@;@;@;0;2;@;BBBB".to_vec();
+        assert_eq!(
+            handler.presentation_state,
+            Some((PresentationStateKind::CursorInformation, data))
+        );
+        assert_eq!(handler.tmux_passthrough, None);
+
+        handler.presentation_state = None;
+        parser.advance(&mut handler, b"\x1bP3$tdata\x1b\\");
+        assert_eq!(handler.presentation_state, None);
+    }
+
+    #[test]
+    fn parse_restore_terminal_state() {
+        let mut parser = Processor::<TestSyncHandler>::new();
+        let mut handler = MockHandler::default();
+
+        parser.advance(&mut handler, b"\x1bP2$p2;1;0;0;0/3;2;50;50;50\x1b\\");
+        let data = b"2;1;0;0;0/3;2;50;50;50".to_vec();
+        assert_eq!(handler.terminal_state, Some((TerminalStateKind::ColorTable, data)));
+
+        // Data is accumulated across reads.
+        parser.advance(&mut handler, b"\x1bP1$pstate");
+        parser.advance(&mut handler, b" report\x1b\\");
+        let data = b"state report".to_vec();
+        assert_eq!(handler.terminal_state, Some((TerminalStateKind::TerminalState, data)));
+        assert_eq!(handler.presentation_state, None);
+
+        handler.terminal_state = None;
+        parser.advance(&mut handler, b"\x1bP3$pdata\x1b\\");
+        assert_eq!(handler.terminal_state, None);
+    }
+
+    #[test]
+    fn parse_soft_font() {
+        let mut parser = Processor::<TestSyncHandler>::new();
+        let mut handler = MockHandler::default();
+
+        parser.advance(&mut handler, b"\x1bP1;33;1;10;0;2;20;0{ @??~~/NN;\x1b\\");
+
+        let header = SoftFontHeader {
+            font_number: 1,
+            starting_char: 33,
+            erase_control: 1,
+            cell_width: 10,
+            font_width: 0,
+            text_or_full_cell: 2,
+            cell_height: 20,
+            charset_size: 0,
+            designator: b" @".to_vec(),
+        };
+        assert_eq!(handler.soft_font, Some((header, b"??~~/NN;".to_vec(), Some(false))));
+    }
+
+    #[test]
+    fn parse_soft_font_defaults() {
+        let mut parser = Processor::<TestSyncHandler>::new();
+        let mut handler = MockHandler::default();
+
+        parser.advance(&mut handler, b"\x1bP{B??\x18");
+
+        let header = SoftFontHeader { designator: b"B".to_vec(), ..Default::default() };
+        assert_eq!(handler.soft_font, Some((header, b"??".to_vec(), Some(true))));
+    }
+
+    #[test]
+    fn parse_invalid_soft_font_designator() {
+        let mut parser = Processor::<TestSyncHandler>::new();
+        let mut handler = MockHandler::default();
+
+        parser.advance(&mut handler, b"\x1bP1{   @??\x1b\\");
+
+        assert_eq!(handler.soft_font, None);
+    }
+
+    #[test]
+    fn parse_non_tmux_dcs() {
+        let mut parser = Processor::<TestSyncHandler>::new();
+        let mut handler = MockHandler::default();
+
+        parser.advance(&mut handler, b"\x1bPtest\x1b\\");
+
+        assert_eq!(handler.tmux_passthrough, None);
+    }
+
+    #[test]
+    fn parse_osc13_set_pointer_color() {
+        let mut parser = Processor::<TestSyncHandler>::new();
+        let mut handler = MockHandler::default();
+
+        parser.advance(&mut handler, b"\x1b]13;#ff0000\x07");
+
+        assert_eq!(handler.color_index, Some(NamedColor::PointerForeground as usize));
+        assert_eq!(handler.color, Some(Rgb { r: 0xFF, g: 0, b: 0 }));
+    }
+
+    #[test]
+    fn parse_osc_dynamic_colors_sequential() {
+        let mut parser = Processor::<TestSyncHandler>::new();
+        let mut handler = MockHandler::default();
+
+        // Subsequent parameters should set HighlightBackground and TektronixCursor.
+        parser.advance(&mut handler, b"\x1b]17;#000000;#00ff00\x07");
+
+        assert_eq!(handler.color_index, Some(NamedColor::TektronixCursor as usize));
+        assert_eq!(handler.color, Some(Rgb { r: 0, g: 0xFF, b: 0 }));
+    }
+
+    #[test]
+    fn parse_osc113_reset_pointer_color() {
+        let mut parser = Processor::<TestSyncHandler>::new();
+        let mut handler = MockHandler::default();
+
+        parser.advance(&mut handler, b"\x1b]113\x07");
+        parser.advance(&mut handler, b"\x1b]119\x07");
+        parser.advance(&mut handler, b"\x1b]110\x07");
+
+        assert_eq!(handler.reset_colors, vec![
+            NamedColor::PointerForeground as usize,
+            NamedColor::HighlightForeground as usize,
+            NamedColor::Foreground as usize,
+        ]);
+    }
+
+    #[test]
+    fn parse_bell_volume() {
+        let mut parser = Processor::<TestSyncHandler>::new();
+        let mut handler = MockHandler::default();
+
+        parser.advance(&mut handler, b"\x1b[2 t");
+        assert_eq!(handler.warning_bell_volume, Some(2));
+
+        parser.advance(&mut handler, b"\x1b[4 u");
+        assert_eq!(handler.margin_bell_volume, Some(4));
+
+        parser.advance(&mut handler, b"\x1b[9 t");
+        assert_eq!(handler.warning_bell_volume, Some(2));
+        assert!(handler.unhandled_csi.is_some());
+    }
+
+    #[test]
+    fn c1_constants() {
+        assert_eq!(C1::IND, 0x84);
+        assert_eq!(C1::CSI, 0x9B);
+        assert_eq!(C1::APC, 0x9F);
+
+        // Each C1 control is equivalent to `ESC Fe`, with `Fe` shifted by 0x40.
+        assert_eq!(C1::CSI, b'[' + 0x40);
+        assert_eq!(C1::ST, b'\\' + 0x40);
+    }
+
+    #[test]
+    fn parse_repeat_preceding() {
+        let mut parser = Processor::<TestSyncHandler>::new();
+        let mut handler = MockHandler::default();
+
+        parser.advance(&mut handler, b"x\x1b[5b");
+
+        assert_eq!(handler.repeated, Some((5, 'x')));
+    }
+
+    #[test]
+    fn parse_scroll_down() {
+        let mut parser = Processor::<TestSyncHandler>::new();
+        let mut handler = MockHandler::default();
+
+        parser.advance(&mut handler, b"\x1b[2^\x1b[3T");
+
+        assert_eq!(handler.scrolled_down, vec![2, 3]);
+    }
+
+    #[test]
+    fn parse_save_restore_private_modes() {
+        let mut parser = Processor::<TestSyncHandler>::new();
+        let mut handler = MockHandler::default();
+        let expected = vec![
+            PrivateMode::Named(NamedPrivateMode::CursorKeys),
+            PrivateMode::Named(NamedPrivateMode::ShowCursor),
+        ];
+
+        parser.advance(&mut handler, b"\x1b[?1;25s");
+        assert_eq!(handler.saved_private_modes, expected);
+        assert!(handler.restored_private_modes.is_empty());
+
+        parser.advance(&mut handler, b"\x1b[?1;25r");
+        assert_eq!(handler.restored_private_modes, expected);
+    }
+
+    #[test]
+    fn random_input_doesnt_panic() {
+        let mut parser = Processor::<TestSyncHandler>::new();
+        let mut handler = MockHandler::default();
+
+        // Xorshift, to get deterministic pseudo-random bytes without dependencies.
+        let mut seed = 0x2545_F491_4F6C_DD1D_u64;
+        let mut bytes = [0; 1024];
+        for _ in 0..256 {
+            for byte in &mut bytes {
+                seed ^= seed << 13;
+                seed ^= seed >> 7;
+                seed ^= seed << 17;
+
+                // Bias towards bytes forming sequences with parameters.
+                const SEQUENCE_BYTES: &[u8] = b"\x1b\x1b[[]];;:?$ 0123456789";
+                *byte = match seed % 4 {
+                    0 => (seed >> 16) as u8,
+                    _ => SEQUENCE_BYTES[(seed >> 8) as usize % SEQUENCE_BYTES.len()],
+                };
+            }
+
+            parser.advance(&mut handler, &bytes);
+        }
+    }
+
+    #[test]
+    fn parse_goto_empty_fields() {
+        let mut parser = Processor::<TestSyncHandler>::new();
+        let mut handler = MockHandler::default();
+
+        for (bytes, goto) in [
+            (&b"\x1b[H"[..], (0, 0)),
+            (b"\x1b[;5H", (0, 4)),
+            (b"\x1b[5;H", (4, 0)),
+            (b"\x1b[;H", (0, 0)),
+            (b"\x1b[0;0H", (0, 0)),
+            (b"\x1b[5H", (4, 0)),
+        ] {
+            handler.goto = None;
+            parser.advance(&mut handler, bytes);
+            assert_eq!(handler.goto, Some(goto), "{:?}", bytes);
+        }
+    }
+
+    #[test]
+    fn parse_vt52_cursor_movement() {
+        let mut parser = Processor::<TestSyncHandler>::new();
+        parser.set_vt52_support(true);
+        let mut handler = MockHandler::default();
+
+        parser.advance(&mut handler, b"\x1b[?2l\x1bA\x1bY%*x");
+
+        assert_eq!(handler.moved_up, 1);
+        assert_eq!(handler.goto, Some((5, 10)));
+        assert_eq!(handler.input, vec!['x']);
+    }
+
+    #[test]
+    fn parse_vt52_exit() {
+        let mut parser = Processor::<TestSyncHandler>::new();
+        parser.set_vt52_support(true);
+        let mut handler = MockHandler::default();
+
+        parser.advance(&mut handler, b"\x1b[?2l\x1b<\x1b[2A\x1bA");
+
+        assert_eq!(handler.moved_up, 2);
+        assert_eq!(handler.unhandled_esc, Some((Vec::new(), b'A')));
+    }
+
+    #[test]
+    fn parse_vt52_unsupported() {
+        let mut parser = Processor::<TestSyncHandler>::new();
+        let mut handler = MockHandler::default();
+
+        parser.advance(&mut handler, b"\x1b[?2l\x1bA\x1bY%*");
+
+        assert_eq!(handler.moved_up, 0);
+        assert_eq!(handler.goto, None);
+        assert_eq!(handler.input, vec!['%', '*']);
+    }
+
+    #[test]
+    fn classify_csi_commands() {
+        struct Classifier(Option<CsiCommand>);
+
+        impl crate::Perform for Classifier {
+            fn csi_dispatch(&mut self, _: &Params, intermediates: &[u8], _: bool, action: char) {
+                self.0 = Some(CsiCommand::parse(action, intermediates));
+            }
+        }
+
+        let sequences: &[(&[u8], CsiCommand)] = &[
+            (b"\x1b[5A", CsiCommand::MoveUp),
+            (b"\x1b[2e", CsiCommand::MoveDown),
+            (b"\x1b[>c", CsiCommand::IdentifyTerminal),
+            (b"\x1b[1;2H", CsiCommand::Goto),
+            (b"\x1b[3f", CsiCommand::Goto),
+            (b"\x1b[?25h", CsiCommand::SetPrivateMode),
+            (b"\x1b[4l", CsiCommand::UnsetMode),
+            (b"\x1b[1;31m", CsiCommand::TerminalAttribute),
+            (b"\x1b[>4;1m", CsiCommand::SetModifyOtherKeys),
+            (b"\x1b[?2026$p", CsiCommand::ReportPrivateMode),
+            (b"\x1b[>q", CsiCommand::ReportTerminalVersion),
+            (b"\x1b[2 q", CsiCommand::SetCursorStyle),
+            (b"\x1b[1;24r", CsiCommand::SetScrollingRegion),
+            (b"\x1b[2^", CsiCommand::ScrollDown),
+            (b"\x1b[>1u", CsiCommand::PushKeyboardMode),
+            (b"\x1b[#{", CsiCommand::PushSgr),
+            (b"\x1b[#p", CsiCommand::PushSgr),
+            (b"\x1b[#}", CsiCommand::PopSgr),
+            (b"\x1b[#q", CsiCommand::PopSgr),
+            (b"\x1b[1$}", CsiCommand::SelectStatusDisplay),
+            (b"\x1b[2$~", CsiCommand::SetStatusDisplayType),
+            (b"\x1b[2*x", CsiCommand::SelectAttributeChangeExtent),
+            (b"\x1b[62;1\"p", CsiCommand::SetConformanceLevel),
+            (b"\x1b[>2t", CsiCommand::SetTitleModes),
+            (b"\x1b[>0T", CsiCommand::ResetTitleModes),
+            (b"\x1b[1 Z", CsiCommand::Unknown),
+            (b"\x1b[?1y", CsiCommand::Unknown),
+        ];
+
+        for (bytes, command) in sequences {
+            let mut classifier = Classifier(None);
+            crate::Parser::new().advance(&mut classifier, bytes);
+            assert_eq!(classifier.0, Some(*command), "{bytes:?}");
+
+            // Unknown commands must be reported as unhandled by the processor.
+            let mut parser = Processor::<TestSyncHandler>::new();
+            let mut handler = MockHandler::default();
+            parser.advance(&mut handler, bytes);
+            assert_eq!(handler.unhandled_csi.is_some(), *command == CsiCommand::Unknown);
+        }
+    }
+
+    #[test]
+    fn parse_sgr_reset() {
+        for input in [&b"\x1b[m"[..], b"\x1b[0m"] {
+            let mut parser = Processor::<TestSyncHandler>::new();
+            let mut handler = MockHandler::default();
+
+            parser.advance(&mut handler, input);
+
+            assert_eq!(handler.attr, Some(Attr::Reset));
+            assert_eq!(handler.attr_count, 1);
+        }
+    }
+
+    #[test]
+    fn parse_sgr_empty_params_reset() {
+        let mut parser = Processor::<TestSyncHandler>::new();
+        let mut handler = MockHandler::default();
+
+        parser.advance(&mut handler, b"\x1b[;m");
+
+        assert_eq!(handler.attr, Some(Attr::Reset));
+        assert_eq!(handler.attr_count, 2);
+    }
+
+    #[test]
+    fn sgr_round_trip() {
+        let inputs: &[&[u8]] = &[
+            b"\x1b[0m",
+            b"\x1b[1m",
+            b"\x1b[4:3m",
+            b"\x1b[9m",
+            b"\x1b[22m",
+            b"\x1b[53m",
+            b"\x1b[31m",
+            b"\x1b[97m",
+            b"\x1b[39m",
+            b"\x1b[49m",
+            b"\x1b[38;5;123m",
+            b"\x1b[48;2;1;2;3m",
+            b"\x1b[58;5;9m",
+            b"\x1b[59m",
+        ];
+
+        for input in inputs {
+            let mut parser = Processor::<TestSyncHandler>::new();
+            let mut handler = MockHandler::default();
+            parser.advance(&mut handler, input);
+            let attr = handler.attr.take().unwrap();
+
+            let params: Vec<String> = attr
+                .to_sgr()
+                .iter()
+                .map(|param| {
+                    let values: Vec<String> = param.iter().map(|value| value.to_string()).collect();
+                    values.join(":")
+                })
+                .collect();
+            let encoded = alloc::format!("\x1b[{}m", params.join(";"));
+            assert_eq!(encoded.as_bytes(), *input);
+
+            parser.advance(&mut handler, encoded.as_bytes());
+            assert_eq!(handler.attr, Some(attr));
+        }
+
+        assert_eq!(Attr::Underline.to_sgr(), vec![vec![4]]);
+        assert_eq!(Attr::DoubleUnderline.to_sgr(), vec![vec![4, 2]]);
+        assert_eq!(
+            Attr::Foreground(Color::Named(NamedColor::Cursor)).to_sgr(),
+            Vec::<Vec<u16>>::new()
+        );
+    }
+
+    #[test]
+    fn cursor_style_to_decscusr() {
+        let style = CursorStyle { shape: CursorShape::Beam, blinking: true };
+        assert_eq!(style.to_decscusr(), encode_cursor_style(style));
+    }
+
+    #[derive(Default)]
+    struct CursorHandler(Vec<(i32, i32)>);
+
+    impl Handler for CursorHandler {
+        fn move_cursor(&mut self, dx: i32, dy: i32) {
+            self.0.push((dx, dy));
+        }
+    }
+
+    #[test]
+    fn parse_cursor_movement() {
+        let mut parser = Processor::<TestSyncHandler>::new();
+        let mut handler = CursorHandler::default();
+
+        parser.advance(&mut handler, b"\x1b[3A\x1b[B\x1b[0C\x1b[65535D\x1b[2e\x1b[4a");
+
+        assert_eq!(handler.0, vec![(0, -3), (0, 1), (1, 0), (-65535, 0), (0, 2), (4, 0)]);
+    }
+
+    #[test]
+    fn move_cursor_default() {
+        let mut parser = Processor::<TestSyncHandler>::new();
+        let mut handler = MockHandler::default();
+
+        parser.advance(&mut handler, b"\x1b[3A");
+
+        assert_eq!(handler.moved_up, 3);
+    }
+
+    #[derive(Default)]
+    struct InputHandler(Vec<char>);
+
+    impl Handler for InputHandler {
+        fn input(&mut self, c: char) {
+            self.0.push(c);
+        }
+    }
+
+    #[test]
+    fn repeat_wide_char() {
+        let mut parser = Processor::<TestSyncHandler>::new();
+        let mut handler = InputHandler::default();
+
+        parser.advance(&mut handler, "漢\x1b[3b".as_bytes());
+
+        assert_eq!(handler.0, vec!['漢'; 4]);
+    }
+
+    #[test]
+    fn repeat_after_control() {
+        let mut parser = Processor::<TestSyncHandler>::new();
+        let mut handler = InputHandler::default();
+
+        parser.advance(&mut handler, b"a\r\x1b[2bb\x1b[1m\x1b[2bc\x1b7\x1b[2bd\x1b[2b\x1b[2b");
+
+        assert_eq!(handler.0, vec!['a', 'b', 'c', 'd', 'd', 'd']);
+    }
+
+    #[test]
+    fn parse_scrolling_region() {
+        let mut parser = Processor::<TestSyncHandler>::new();
+        let mut handler = MockHandler::default();
+
+        parser.advance(&mut handler, b"\x1b[5;20r");
+        assert_eq!(handler.scrolling_region, Some((5, Some(20))));
+
+        parser.advance(&mut handler, b"\x1b[r");
+        assert_eq!(handler.scrolling_region, Some((1, None)));
+
+        parser.advance(&mut handler, b"\x1b[;10r");
+        assert_eq!(handler.scrolling_region, Some((1, Some(10))));
+    }
+
+    #[test]
+    fn parse_scrolling_region_extra_params() {
+        let mut parser = Processor::<TestSyncHandler>::new();
+        let mut handler = MockHandler::default();
+
+        parser.advance(&mut handler, b"\x1b[1;24;99;3r");
+
+        assert_eq!(handler.scrolling_region, Some((1, Some(24))));
+    }
+
+    #[test]
+    fn parse_inverted_scrolling_region() {
+        let mut parser = Processor::<TestSyncHandler>::new();
+        let mut handler = MockHandler::default();
+
+        parser.advance(&mut handler, b"\x1b[20;5r");
+        parser.advance(&mut handler, b"\x1b[7;7r");
+        parser.advance(&mut handler, b"\x1b[;1r");
+
+        assert_eq!(handler.scrolling_region, None);
+        assert_eq!(handler.scrolling_region_resets, 0);
+        assert_eq!(handler.unhandled_csi, Some(('r', vec![vec![0], vec![1]], Vec::new())));
+    }
+
+    #[test]
+    fn parse_scrolling_region_reset() {
+        let mut parser = Processor::<TestSyncHandler>::new();
+        let mut handler = MockHandler::default();
+
+        parser.advance(&mut handler, b"\x1b[r");
+        parser.advance(&mut handler, b"\x1b[;r");
+        parser.advance(&mut handler, b"\x1b[1r");
+        parser.advance(&mut handler, b"\x1b[0;0r");
+        assert_eq!(handler.scrolling_region_resets, 4);
+        assert_eq!(handler.scrolling_region, Some((1, None)));
+
+        parser.advance(&mut handler, b"\x1b[3r");
+        assert_eq!(handler.scrolling_region_resets, 4);
+        assert_eq!(handler.scrolling_region, Some((3, None)));
+    }
+
+    #[test]
+    fn parse_title() {
+        let mut parser = Processor::<TestSyncHandler>::new();
+        let mut handler = MockHandler::default();
+
+        parser.advance(&mut handler, b"\x1b]2;a;b\x07");
+        assert_eq!(handler.title, Some(Some("a;b".into())));
+
+        parser.advance(&mut handler, b"\x1b]2;  spaced  \x07");
+        assert_eq!(handler.title, Some(Some("spaced".into())));
+
+        parser.advance(&mut handler, b"\x1b]0;   \x07");
+        assert_eq!(handler.title, Some(Some(String::new())));
+    }
+
+    #[test]
+    fn parse_unknown_private_modes() {
+        let mut parser = Processor::<TestSyncHandler>::new();
+        let mut handler = MockHandler::default();
+
+        parser.advance(&mut handler, b"\x1b[?9999;7h\x1b[?25;1l\x1b[?8;9000;9001l");
+
+        assert_eq!(handler.unknown_private_modes, vec![vec![9999], vec![8, 9000, 9001]]);
+    }
+
+    #[test]
+    fn parse_iterm2_inline_image() {
+        let mut parser = Processor::<TestSyncHandler>::new();
+        let mut handler = MockHandler::default();
+
+        parser.advance(&mut handler, b"\x1b]1337;File=inline=1:SGVsbG8=\x07");
+        assert_eq!(
+            handler.iterm2,
+            Some((
+                vec![("File".into(), "".into()), ("inline".into(), "1".into())],
+                Some(b"SGVsbG8=".to_vec())
+            ))
+        );
+
+        parser.advance(&mut handler, b"\x1b]1337;File=name=YS50eHQ=;size=5;inline=0:SGVsbG8=\x07");
+        assert_eq!(
+            handler.iterm2,
+            Some((
+                vec![
+                    ("File".into(), "".into()),
+                    ("name".into(), "YS50eHQ=".into()),
+                    ("size".into(), "5".into()),
+                    ("inline".into(), "0".into()),
+                ],
+                Some(b"SGVsbG8=".to_vec())
+            ))
+        );
+    }
+
+    #[test]
+    fn parse_iterm2_commands() {
+        let mut parser = Processor::<TestSyncHandler>::new();
+        let mut handler = MockHandler::default();
+
+        parser.advance(&mut handler, b"\x1b]1337;CurrentDir=/tmp/a:b\x07");
+        assert_eq!(handler.iterm2, Some((vec![("CurrentDir".into(), "/tmp/a:b".into())], None)));
+
+        parser.advance(&mut handler, b"\x1b]1337;SetMark\x1b\\");
+        assert_eq!(handler.iterm2, Some((vec![("SetMark".into(), "".into())], None)));
+
+        handler.iterm2 = None;
+        parser.advance(&mut handler, b"\x1b]1337;\xff\x07\x1b]1337\x07");
+        assert_eq!(handler.iterm2, None);
+        assert_eq!(handler.unhandled_osc, Some((vec![b"1337".to_vec()], true)));
+    }
+
+    #[test]
+    fn parse_raw_osc() {
+        let mut parser = Processor::<TestSyncHandler>::new();
+        let mut handler = MockHandler::default();
+
+        parser.advance(&mut handler, b"\x1b]2;first\x07");
+        assert_eq!(handler.raw_osc, Some(vec![b"2".to_vec(), b"first".to_vec()]));
+        assert_eq!(handler.title, Some(Some("first".into())));
+
+        handler.suppress_osc = true;
+        parser.advance(&mut handler, b"\x1b]0;second\x07\x1b]9999\x07");
+        assert_eq!(handler.raw_osc, Some(vec![b"9999".to_vec()]));
+        assert_eq!(handler.title, Some(Some("first".into())));
+        assert_eq!(handler.unhandled_osc, None);
+    }
+
+    #[test]
+    fn parse_untrimmed_title() {
+        let mut parser = Processor::<TestSyncHandler>::new();
+        parser.set_trim_titles(false);
+        let mut handler = MockHandler::default();
+
+        parser.advance(&mut handler, b"\x1b]2;  spaced ; out  \x07");
+        assert_eq!(handler.title, Some(Some("  spaced ; out  ".into())));
+
+        parser.advance(&mut handler, b"\x1b]0;   \x07");
+        assert_eq!(handler.title, Some(Some("   ".into())));
+    }
+
+    #[test]
+    fn parse_media_copy() {
+        let mut parser = Processor::<TestSyncHandler>::new();
+        let mut handler = MockHandler::default();
+
+        parser.advance(&mut handler, b"\x1b[0i");
+        assert_eq!(handler.media_copy, Some((MediaCopyMode::PrintScreen, false)));
+
+        parser.advance(&mut handler, b"\x1b[?5i");
+        assert_eq!(handler.media_copy, Some((MediaCopyMode::StartPrintLog, true)));
+
+        handler.media_copy = None;
+        parser.advance(&mut handler, b"\x1b[7i");
+        assert_eq!(handler.media_copy, None);
+        assert_eq!(handler.unhandled_csi, Some(('i', vec![vec![7]], Vec::new())));
+    }
+
+    #[test]
+    fn param_enums_try_from() {
+        assert_eq!(LineClearMode::try_from(2), Ok(LineClearMode::All));
+        assert_eq!(LineClearMode::try_from(3), Err(()));
+        assert_eq!(ClearMode::try_from(3), Ok(ClearMode::Saved));
+        assert_eq!(ClearMode::try_from(4), Err(()));
+        assert_eq!(TabulationClearMode::try_from(0), Ok(TabulationClearMode::Current));
+        assert_eq!(TabulationClearMode::try_from(3), Ok(TabulationClearMode::All));
+        assert_eq!(TabulationClearMode::try_from(1), Err(()));
+        assert_eq!(ScpCharPath::try_from(2), Ok(ScpCharPath::RTL));
+        assert_eq!(ScpCharPath::try_from(3), Err(()));
+        assert_eq!(ScpUpdateMode::try_from(1), Ok(ScpUpdateMode::DataToPresentation));
+        assert_eq!(ScpUpdateMode::try_from(3), Err(()));
+        assert_eq!(CursorShape::try_from(4), Ok(CursorShape::Underline));
+        assert_eq!(CursorShape::try_from(5), Ok(CursorShape::Beam));
+        assert_eq!(CursorShape::try_from(0), Err(()));
+        assert_eq!(CursorShape::try_from(7), Err(()));
+    }
+
+    #[test]
+    fn parse_invalid_clear_modes() {
+        let mut parser = Processor::<TestSyncHandler>::new();
+        let mut handler = MockHandler::default();
+
+        parser.advance(&mut handler, b"\x1b[4J");
+        assert_eq!(handler.unhandled_csi, Some(('J', vec![vec![4]], Vec::new())));
+
+        parser.advance(&mut handler, b"\x1b[1;3 k");
+        assert_eq!(handler.scp, None);
+        assert_eq!(handler.unhandled_csi, Some(('k', vec![vec![1], vec![3]], vec![b' '])));
+
+        parser.advance(&mut handler, b"\x1b[7 q");
+        assert_eq!(handler.cursor_style, None);
+        assert_eq!(handler.unhandled_csi, Some(('q', vec![vec![7]], vec![b' '])));
+    }
+
+    #[test]
+    fn parse_scp() {
+        let mut parser = Processor::<TestSyncHandler>::new();
+        let mut handler = MockHandler::default();
+
+        parser.advance(&mut handler, b"\x1b[2;1 k");
+        assert_eq!(handler.scp, Some((ScpCharPath::RTL, ScpUpdateMode::DataToPresentation)));
+
+        parser.advance(&mut handler, b"\x1b[ k");
+        assert_eq!(
+            handler.scp,
+            Some((ScpCharPath::Default, ScpUpdateMode::ImplementationDependant))
+        );
+
+        parser.advance(&mut handler, b"\x1b[;2 k");
+        assert_eq!(handler.scp, Some((ScpCharPath::Default, ScpUpdateMode::PresentationToData)));
+    }
+
+    #[test]
+    fn parse_title_modes() {
+        let mut parser = Processor::<TestSyncHandler>::new();
+        let mut handler = MockHandler::default();
+
+        parser.advance(&mut handler, b"\x1b[>2t");
+        assert_eq!(handler.title_modes, TitleModes::SET_UTF8);
+
+        parser.advance(&mut handler, b"\x1b[>0;1;3t");
+        assert_eq!(handler.title_modes, TitleModes::all());
+
+        parser.advance(&mut handler, b"\x1b[>0;1T");
+        assert_eq!(handler.title_modes, TitleModes::SET_UTF8 | TitleModes::QUERY_UTF8);
+
+        parser.advance(&mut handler, b"\x1b[>2;4T");
+        assert_eq!(handler.title_modes, TitleModes::SET_UTF8 | TitleModes::QUERY_UTF8);
+        assert_eq!(handler.unhandled_csi, Some(('T', vec![vec![2], vec![4]], vec![b'>'])));
+    }
+
+    #[test]
+    fn parse_ansi_conformance_level() {
+        let mut parser = Processor::<TestSyncHandler>::new();
+        let mut handler = MockHandler::default();
+
+        parser.advance(&mut handler, b"\x1b L");
+        assert_eq!(handler.conformance_level, Some((1, false)));
+
+        parser.advance(&mut handler, b"\x1b M");
+        assert_eq!(handler.conformance_level, Some((2, false)));
+
+        parser.advance(&mut handler, b"\x1b N");
+        assert_eq!(handler.conformance_level, Some((3, false)));
+    }
+
+    #[test]
+    fn parse_decscl() {
+        let mut parser = Processor::<TestSyncHandler>::new();
+        let mut handler = MockHandler::default();
+
+        parser.advance(&mut handler, b"\x1b[61\"p");
+        assert_eq!(handler.conformance_level, Some((1, false)));
+
+        parser.advance(&mut handler, b"\x1b[64;1\"p");
+        assert_eq!(handler.conformance_level, Some((4, false)));
+
+        parser.advance(&mut handler, b"\x1b[65;2\"p");
+        assert_eq!(handler.conformance_level, Some((5, true)));
+
+        parser.advance(&mut handler, b"\x1b[62\"p");
+        assert_eq!(handler.conformance_level, Some((2, true)));
+
+        parser.advance(&mut handler, b"\x1b[66\"p");
+        assert_eq!(handler.conformance_level, Some((2, true)));
+        assert_eq!(handler.unhandled_csi, Some(('p', vec![vec![66]], vec![b'"'])));
+    }
+
+    #[test]
+    fn parse_status_display() {
+        let mut parser = Processor::<TestSyncHandler>::new();
+        let mut handler = MockHandler::default();
+
+        parser.advance(&mut handler, b"\x1b[1$}");
+        assert_eq!(handler.status_display, Some(StatusDisplay::StatusLine));
+
+        parser.advance(&mut handler, b"\x1b[$}");
+        assert_eq!(handler.status_display, Some(StatusDisplay::Main));
+
+        parser.advance(&mut handler, b"\x1b[2$~");
+        assert_eq!(handler.status_display_type, Some(StatusDisplayType::HostWritable));
+
+        parser.advance(&mut handler, b"\x1b[3$~");
+        assert_eq!(handler.status_display_type, Some(StatusDisplayType::HostWritable));
+        assert_eq!(handler.unhandled_csi, Some(('~', vec![vec![3]], vec![b'$'])));
+    }
+
+    #[test]
+    fn supported_sequence_ids() {
+        let supported = supported_sequences();
+
+        assert!(supported.contains(&SequenceId::Csi(CsiCommand::TerminalAttribute)));
+        assert!(supported.contains(&SequenceId::Osc(52)));
+        assert!(supported.contains(&SequenceId::Esc(b"(", b'0')));
+        assert!(supported.contains(&SequenceId::Dcs(b"", '{')));
+
+        assert!(!supported.contains(&SequenceId::Csi(CsiCommand::Unknown)));
+        assert!(!supported.contains(&SequenceId::Osc(7)));
+        assert!(!supported.contains(&SequenceId::Esc(b"", b'n')));
+        assert!(!supported.contains(&SequenceId::Dcs(b"+", 'q')));
+
+        // All listed escapes are dispatched.
+        let mut parser = Processor::<TestSyncHandler>::new();
+        let mut handler = MockHandler::default();
+        for sequence in supported {
+            if let SequenceId::Esc(intermediates, byte) = *sequence {
+                parser.advance(&mut handler, b"\x1b");
+                parser.advance(&mut handler, intermediates);
+                parser.advance(&mut handler, &[byte]);
+            }
+        }
+        assert_eq!(handler.unhandled_esc, None);
+    }
+
+    #[test]
+    fn supported_sequences_match_dispatch() {
+        let supported = supported_sequences();
+
+        // Every classified CSI is listed and dispatched for some of the sample
+        // parameters.
+        let mut dispatched = Vec::new();
+        for private in [&b""[..], b"<", b"=", b">", b"?"] {
+            for trailing in iter::once(None).chain((0x20..=0x2F).map(Some)) {
+                let mut intermediates = private.to_vec();
+                intermediates.extend(trailing);
+
+                for action in 0x40..=0x7E {
+                    let command = CsiCommand::parse(action as char, &intermediates);
+                    if command == CsiCommand::Unknown {
+                        continue;
+                    }
+                    assert!(supported.contains(&SequenceId::Csi(command)), "{:?}", command);
+
+                    for params in [&b""[..], b"1", b"2", b"4", b"5", b"1;2", b"4;1", b"14", b"62;1"]
+                    {
+                        let mut parser = Processor::<TestSyncHandler>::new();
+                        let mut handler = MockHandler::default();
+                        parser.advance(&mut handler, b"\x1b[");
+                        parser.advance(&mut handler, private);
+                        parser.advance(&mut handler, params);
+                        parser.advance(&mut handler, &intermediates[private.len()..]);
+                        parser.advance(&mut handler, &[action]);
+                        if handler.unhandled_csi.is_none() {
+                            dispatched.push(command);
+                        }
+                    }
+                }
+            }
+        }
+        for sequence in supported {
+            if let SequenceId::Csi(command) = sequence {
+                assert!(dispatched.contains(command), "{:?}", command);
+            }
+        }
+
+        // Only listed OSCs are dispatched.
+        let mut parser = Processor::<TestSyncHandler>::new();
+        let mut handler = MockHandler::default();
+        for number in 0..=1400 {
+            let mut handled = false;
+            for payload in [
+                &b""[..],
+                b";?",
+                b";1;?",
+                b";c;?",
+                b";;https://x",
+                b";arrow",
+                b";CursorShape=1",
+                b";a=b",
+            ] {
+                handler.unhandled_osc = None;
+                parser.advance(&mut handler, alloc::format!("\x1b]{number}").as_bytes());
+                parser.advance(&mut handler, payload);
+                parser.advance(&mut handler, b"\x07");
+                handled |= handler.unhandled_osc.is_none();
+            }
+            assert_eq!(handled, supported.contains(&SequenceId::Osc(number)), "OSC {number}");
+        }
+    }
+
+    #[test]
+    fn advance_tracked_queries() {
+        let mut parser = Processor::<TestSyncHandler>::new();
+        let mut handler = MockHandler::default();
+
+        // Mode, device status, device attribute and version queries.
+        let outcome = parser.advance_tracked(&mut handler, b"\x1b[?2026$p\x1b[6n\x1b[c\x1b[>q\x07");
+        assert_eq!(outcome, AdvanceOutcome { visible_change: false, sync_pending: false });
+
+        let outcome = parser.advance_tracked(&mut handler, b"\x1b[?1$pa");
+        assert_eq!(outcome, AdvanceOutcome { visible_change: true, sync_pending: false });
+
+        // Keypad mode changes.
+        assert!(!parser.advance_tracked(&mut handler, b"\x1b=\x1b>").visible_change);
+
+        // Color queries.
+        let outcome = parser.advance_tracked(&mut handler, b"\x1b]11;?\x07\x1b]4;1;?\x07");
+        assert!(!outcome.visible_change);
+
+        for visible in [&b"\x1b[2J"[..], b"\x1b[1m", b"\x1b[S", b"\r", b"\x1bM", b"\x1b#8"] {
+            let outcome = parser.advance_tracked(&mut handler, visible);
+            assert!(outcome.visible_change, "{:?}", visible);
+        }
+    }
+
+    #[test]
+    fn advance_tracked_colors() {
+        let mut parser = Processor::<TestSyncHandler>::new();
+        let mut handler = MockHandler::default();
+
+        let outcome = parser.advance_tracked(&mut handler, b"\x1b]2;title\x07\x1b]8;;\x07");
+        assert!(!outcome.visible_change);
+
+        for visible in [
+            &b"\x1b]4;1;#ff0000\x07"[..],
+            b"\x1b]10;#ff0000\x07",
+            b"\x1b]19;#ff0000\x07",
+            b"\x1b]104\x07",
+            b"\x1b]110\x07",
+            b"\x1b]119\x07",
+        ] {
+            let outcome = parser.advance_tracked(&mut handler, visible);
+            assert!(outcome.visible_change, "{:?}", visible);
+        }
+    }
+
+    #[test]
+    fn advance_tracked_vt52_cursor_address() {
+        let mut parser = Processor::<TestSyncHandler>::new();
+        parser.set_vt52_support(true);
+        let mut handler = MockHandler::default();
+
+        parser.advance(&mut handler, b"\x1b[?2l\x1bY%");
+        let outcome = parser.advance_tracked(&mut handler, b"*");
+        assert!(outcome.visible_change);
+        assert_eq!(handler.goto, Some((5, 10)));
+    }
+
+    #[test]
+    fn advance_tracked_sync_update() {
+        let mut parser = Processor::<TestSyncHandler>::new();
+        let mut handler = MockHandler::default();
+
+        let outcome = parser.advance_tracked(&mut handler, b"\x1b[?2026ha\x1b[2J");
+        assert_eq!(outcome, AdvanceOutcome { visible_change: false, sync_pending: true });
+
+        let outcome = parser.advance_tracked(&mut handler, b"\x1b[?2026l");
+        assert_eq!(outcome, AdvanceOutcome { visible_change: true, sync_pending: false });
+    }
+
+    #[test]
+    fn parse_decaln() {
+        let mut parser = Processor::<TestSyncHandler>::new();
+        let mut handler = MockHandler::default();
+
+        parser.advance(&mut handler, b"\x1b#8");
+        assert_eq!(handler.decalns, 1);
+
+        // Restoring the cursor without the intermediate.
+        parser.advance(&mut handler, b"\x1b8");
+        assert_eq!(handler.decalns, 1);
+        assert_eq!(handler.unhandled_esc, None);
+    }
+
+    #[test]
+    fn parse_line_attributes_not_decaln() {
+        let mut parser = Processor::<TestSyncHandler>::new();
+        let mut handler = MockHandler::default();
+
+        // DECDHL, DECSWL and DECDWL.
+        for byte in b'3'..=b'6' {
+            parser.advance(&mut handler, &[0x1B, b'#', byte]);
+            assert_eq!(handler.unhandled_esc, Some((vec![b'#'], byte)));
+        }
+
+        assert_eq!(handler.decalns, 0);
+    }
+
+    #[test]
+    fn parse_mouse_cursor_icon() {
+        let mut parser = Processor::<TestSyncHandler>::new();
+        let mut handler = MockHandler::default();
+
+        parser.advance(&mut handler, b"\x1b]22;pointer\x07");
+        assert_eq!(handler.mouse_cursor_icon, Some(CursorIcon::Pointer));
+        assert_eq!(handler.mouse_cursor_icon_raw, None);
+
+        parser.advance(&mut handler, b"\x1b]22;x-custom-hand\x07");
+        assert_eq!(handler.mouse_cursor_icon, Some(CursorIcon::Pointer));
+        assert_eq!(handler.mouse_cursor_icon_raw.as_deref(), Some("x-custom-hand"));
+    }
+
+    #[test]
+    fn parse_title_stack_targets() {
+        let mut parser = Processor::<TestSyncHandler>::new();
+        let mut handler = MockHandler::default();
+
+        parser.advance(&mut handler, b"\x1b[22;1t\x1b[23;2t\x1b[22t\x1b[23;0t\x1b[22;3t");
+
+        assert_eq!(handler.title_stack, vec![
+            (true, TitleTarget::Icon),
+            (false, TitleTarget::Window),
+            (true, TitleTarget::Both),
+            (false, TitleTarget::Both),
+        ]);
+        assert_eq!(handler.unhandled_csi, Some(('t', vec![vec![22], vec![3]], Vec::new())));
+    }
+
+    #[test]
+    fn parse_attribute_change_extent() {
+        let mut parser = Processor::<TestSyncHandler>::new();
+        let mut handler = MockHandler::default();
+
+        parser.advance(&mut handler, b"\x1b[2*x");
+        assert_eq!(handler.attribute_change_extent, Some(AttributeChangeExtent::Rectangle));
+
+        parser.advance(&mut handler, b"\x1b[*x");
+        assert_eq!(handler.attribute_change_extent, Some(AttributeChangeExtent::Stream));
+
+        parser.advance(&mut handler, b"\x1b[2*x\x1b[1*x");
+        assert_eq!(handler.attribute_change_extent, Some(AttributeChangeExtent::Stream));
+
+        parser.advance(&mut handler, b"\x1b[3*x");
+        assert_eq!(handler.attribute_change_extent, Some(AttributeChangeExtent::Stream));
+        assert_eq!(handler.unhandled_csi, Some(('x', vec![vec![3]], vec![b'*'])));
+    }
+
+    #[test]
+    fn parse_push_pop_sgr() {
+        let mut parser = Processor::<TestSyncHandler>::new();
+        let mut handler = MockHandler::default();
+
+        parser.advance(&mut handler, b"\x1b[#{");
+        assert_eq!(handler.sgr_stack, vec![None]);
+
+        parser.advance(&mut handler, b"\x1b[1;30;6#{");
+        let items = SgrStackItems::BOLD | SgrStackItems::FOREGROUND;
+        assert_eq!(handler.sgr_stack, vec![None, Some(items)]);
+
+        parser.advance(&mut handler, b"\x1b[#}");
+        assert_eq!(handler.sgr_stack, vec![None]);
+
+        parser.advance(&mut handler, b"\x1b[#}");
+        assert_eq!(handler.sgr_stack, Vec::new());
+    }
+
+    #[test]
+    fn parse_push_pop_sgr_alias() {
+        let mut parser = Processor::<TestSyncHandler>::new();
+        let mut handler = MockHandler::default();
+
+        parser.advance(&mut handler, b"\x1b[1;4#p");
+        let items = SgrStackItems::BOLD | SgrStackItems::UNDERLINE;
+        assert_eq!(handler.sgr_stack, vec![Some(items)]);
+
+        parser.advance(&mut handler, b"\x1b[#p");
+        assert_eq!(handler.sgr_stack, vec![Some(items), None]);
+
+        parser.advance(&mut handler, b"\x1b[#q\x1b[#q");
+        assert_eq!(handler.sgr_stack, Vec::new());
+    }
+
+    #[test]
+    fn parse_sgr_overline() {
+        let mut parser = Processor::<TestSyncHandler>::new();
+        let mut handler = MockHandler::default();
+
+        parser.advance(&mut handler, b"\x1b[53m");
+        assert_eq!(handler.attr, Some(Attr::Overline));
+
+        parser.advance(&mut handler, b"\x1b[55m");
+        assert_eq!(handler.attr, Some(Attr::CancelOverline));
+
+        assert!(handler.unhandled_sgr.is_empty());
+    }
+
+    #[test]
+    fn parse_unhandled_sgr() {
+        let mut parser = Processor::<TestSyncHandler>::new();
+        let mut handler = MockHandler::default();
+
+        parser.advance(&mut handler, b"\x1b[1;11;26m");
+
+        assert_eq!(handler.attr, Some(Attr::Bold));
+        assert_eq!(handler.unhandled_sgr, vec![vec![11], vec![26]]);
+    }
+
+    #[cfg(feature = "wide-chars")]
+    #[test]
+    fn input_with_width() {
+        fn char_width(c: char) -> usize {
+            match c {
+                '\u{1100}'..='\u{115f}' | '\u{2e80}'..='\u{a4cf}' | '\u{ac00}'..='\u{d7a3}' => 2,
+                _ => 1,
+            }
+        }
+
+        let mut parser = Processor::<TestSyncHandler>::new();
+        let mut handler = MockHandler::default();
+
+        parser.advance(&mut handler, "a漢".as_bytes());
+        assert_eq!(handler.input, vec!['a', '漢']);
+        assert_eq!(handler.widths, Vec::new());
+
+        parser.set_char_width(char_width);
+        parser.advance(&mut handler, "a漢".as_bytes());
+        assert_eq!(handler.widths, vec![('a', 1), ('漢', 2)]);
+    }
+
+    #[test]
+    fn end_partial_utf8() {
+        let mut parser = Processor::<TestSyncHandler>::new();
+        let mut handler = MockHandler::default();
+
+        parser.advance(&mut handler, &"a漢".as_bytes()[..3]);
+        assert_eq!(handler.input, vec!['a']);
+
+        parser.end(&mut handler);
+        assert_eq!(handler.input, vec!['a', '�']);
+    }
+
+    #[test]
+    fn end_sync_update() {
+        let mut parser = Processor::<TestSyncHandler>::new();
+        let mut handler = MockHandler::default();
+
+        parser.advance(&mut handler, b"\x1b[?2026hab\xe6");
+        assert!(handler.input.is_empty());
+
+        parser.end(&mut handler);
+        assert_eq!(handler.input, vec!['a', 'b', '�']);
+        assert!(!parser.sync_timeout().pending_timeout());
+        assert_eq!(handler.sync_aborts, vec![SyncAbortReason::Explicit]);
+    }
+
+    #[test]
+    fn sync_update_aborted() {
+        let mut parser = Processor::<TestSyncHandler>::new();
+        let mut handler = MockHandler::default();
+
+        // Terminated synchronized updates are not aborted.
+        parser.advance(&mut handler, b"\x1b[?2026ha\x1b[?2026l");
+        assert!(handler.sync_aborts.is_empty());
+
+        parser.advance(&mut handler, b"\x1b[?2026h");
+        parser.advance(&mut handler, "a".repeat(SYNC_BUFFER_SIZE).as_bytes());
+        assert_eq!(handler.sync_aborts, vec![SyncAbortReason::BufferFull]);
+
+        parser.advance(&mut handler, b"\x1b[?2026h");
+        parser.stop_sync(&mut handler);
+        assert_eq!(handler.sync_aborts, vec![
+            SyncAbortReason::BufferFull,
+            SyncAbortReason::Timeout
+        ]);
+    }
+
+    #[test]
+    fn migrate_sync_buffer() {
+        let sync_update = NamedPrivateMode::SyncUpdate.into();
+
+        let mut parser = Processor::<TestSyncHandler>::new();
+        parser.set_track_private_modes(true);
+        let mut handler = MockHandler::default();
+
+        parser.advance(&mut handler, b"\x1b[?2026h\x1b[31m");
+        let buffer = parser.take_sync_buffer(&mut handler);
+        assert_eq!(buffer, b"\x1b[31m");
+        assert_eq!(parser.sync_bytes_count(), 0);
+        assert!(!parser.sync_timeout().pending_timeout());
+        assert_eq!(handler.private_modes, vec![(sync_update, true), (sync_update, false)]);
+
+        parser.advance(&mut handler, b"\x1b[?2026$p");
+        assert_eq!(handler.private_mode_states, vec![(sync_update, ModeState::Reset)]);
+
+        let mut parser = Processor::<TestSyncHandler>::new();
+        parser.set_track_private_modes(true);
+        let mut handler = MockHandler::default();
+        parser.set_sync_buffer(&mut handler, buffer);
+        assert!(parser.sync_timeout().pending_timeout());
+        assert!(handler.attr.is_none());
+        assert_eq!(handler.private_modes, vec![(sync_update, true)]);
+
+        parser.stop_sync(&mut handler);
+        assert_eq!(handler.attr, Some(Attr::Foreground(Color::Named(NamedColor::Red))));
+        assert!(!parser.sync_timeout().pending_timeout());
+        assert_eq!(handler.private_modes, vec![(sync_update, true), (sync_update, false)]);
+
+        parser.advance(&mut handler, b"\x1b[?2026$p");
+        assert_eq!(handler.private_mode_states, vec![(sync_update, ModeState::Reset)]);
+    }
+
+    #[test]
+    fn parse_c1_transmission() {
+        let mut parser = Processor::<TestSyncHandler>::new();
+        let mut handler = MockHandler::default();
+
+        parser.advance(&mut handler, b"\x1b G");
+        assert_eq!(handler.c1_transmission, Some(true));
+
+        parser.advance(&mut handler, b"\x1b F");
+        assert_eq!(handler.c1_transmission, Some(false));
+    }
+
+    #[test]
+    fn parse_clipboard_selectors() {
+        let mut parser = Processor::<TestSyncHandler>::new();
+        let mut handler = MockHandler::default();
+
+        parser.advance(&mut handler, b"\x1b]52;pc;dnRlIQ==\x07\x1b]52;;?\x07\x1b]52;s07;?\x07");
+        assert_eq!(handler.clipboard_stores, vec![(b"pc".to_vec(), b"dnRlIQ==".to_vec())]);
+        assert_eq!(handler.clipboard_loads, vec![b"c".to_vec(), b"s07".to_vec()]);
+
+        // Invalid selectors are rejected.
+        parser.advance(&mut handler, b"\x1b]52;c8;?\x07");
+        assert_eq!(handler.clipboard_loads.len(), 2);
+        assert!(handler.unhandled_osc.is_some());
+    }
+
+    #[test]
+    fn clipboard_selectors_default() {
+        #[derive(Default)]
+        struct ClipboardHandler {
+            stores: Vec<(u8, Vec<u8>)>,
+            loads: Vec<u8>,
+        }
+
+        impl Handler for ClipboardHandler {
+            fn clipboard_store(&mut self, clipboard: u8, data: &[u8]) {
+                self.stores.push((clipboard, data.to_vec()));
+            }
+
+            fn clipboard_load(&mut self, clipboard: u8, _terminator: &str) {
+                self.loads.push(clipboard);
+            }
+        }
+
+        let mut parser = Processor::<TestSyncHandler>::new();
+        let mut handler = ClipboardHandler::default();
+
+        parser.advance(&mut handler, b"\x1b]52;pc;dnRlIQ==\x07\x1b]52;;?\x07");
+
+        assert_eq!(handler.stores, vec![(b'p', b"dnRlIQ==".to_vec())]);
+        assert_eq!(handler.loads, vec![b'c']);
+
+        // External callers might pass an empty selection.
+        handler.clipboard_store_selectors(&[], b"data");
+        handler.clipboard_load_selectors(&[], "\x07");
+        assert_eq!(handler.stores[1], (b'c', b"data".to_vec()));
+        assert_eq!(handler.loads, vec![b'c', b'c']);
+    }
+
+    #[test]
+    fn base64_stream_chunks() {
+        let data: Vec<u8> = (0..=255).chain(0..100).collect();
+        for len in 0..data.len().min(8) {
+            let mut encoded = String::new();
+            encode_base64(&mut encoded, &data[..len]);
+
+            let mut one_shot = Vec::new();
+            let mut decoder = Base64Stream::new();
+            decoder.decode(encoded.as_bytes(), &mut one_shot).unwrap();
+            decoder.finish(&mut one_shot).unwrap();
+            assert_eq!(one_shot, &data[..len]);
+        }
+
+        let mut encoded = String::new();
+        encode_base64(&mut encoded, &data);
+
+        for chunk_size in [1, 2, 3, 5, 7, 11, 64, 1000] {
+            let mut decoded = Vec::new();
+            let mut decoder = Base64Stream::new();
+            for chunk in encoded.as_bytes().chunks(chunk_size) {
+                decoder.decode(chunk, &mut decoded).unwrap();
+            }
+            decoder.finish(&mut decoded).unwrap();
+            assert_eq!(decoded, data, "chunk size {chunk_size}");
+        }
+    }
+
+    #[test]
+    fn base64_stream_padding() {
+        let mut decoded = Vec::new();
+        let mut decoder = Base64Stream::new();
+
+        // Padding split from its group.
+        decoder.decode(b"dnRl", &mut decoded).unwrap();
+        decoder.decode(b"IQ=", &mut decoded).unwrap();
+        decoder.decode(b"=", &mut decoded).unwrap();
+        decoder.finish(&mut decoded).unwrap();
+        assert_eq!(decoded, b"vte!");
+
+        // Unpadded final group.
+        decoded.clear();
+        decoder.decode(b"dnRlIQ", &mut decoded).unwrap();
+        decoder.finish(&mut decoded).unwrap();
+        assert_eq!(decoded, b"vte!");
+
+        // Invalid data.
+        assert_eq!(Base64Stream::new().decode(b"dn*l", &mut decoded), Err(Base64Error));
+        assert_eq!(Base64Stream::new().decode(b"dnRl=", &mut decoded), Err(Base64Error));
+        assert_eq!(Base64Stream::new().decode(b"dn==dn==", &mut decoded), Err(Base64Error));
+
+        let mut decoder = Base64Stream::new();
+        decoder.decode(b"dnRlI", &mut decoded).unwrap();
+        assert_eq!(decoder.finish(&mut decoded), Err(Base64Error));
+    }
+
+    #[test]
+    fn format_clipboard() {
+        assert_eq!(format_clipboard_response(b'c', b"hello", "\x07"), "\x1b]52;c;aGVsbG8=\x07");
+        assert_eq!(format_clipboard_response(b'p', b"vte!", "\x1b\\"), "\x1b]52;p;dnRlIQ==\x1b\\");
+        assert_eq!(format_clipboard_response(b'c', b"", "\x07"), "\x1b]52;c;\x07");
+
+        let mut encoded = String::new();
+        encode_base64(&mut encoded, &[0xFB, 0xFF, 0xBF]);
+        assert_eq!(encoded, "+/+/");
+    }
+
+    #[test]
+    fn encode_c1_replies() {
+        assert_eq!(encode_c1_controls(&format_xtversion("vte", "1.0")), b"\x90>|vte 1.0\x9c");
+        assert_eq!(encode_c1_controls(&format_text_area_chars(24, 80)), b"\x9b8;24;80t");
+        assert_eq!(encode_c1_controls("\x1b7\x1b"), b"\x1b7\x1b");
+    }
+
+    #[test]
+    fn report_tracked_private_modes() {
+        let mut parser = Processor::<TestSyncHandler>::new();
+        parser.set_track_private_modes(true);
+        let mut handler = MockHandler::default();
+
+        parser.advance(&mut handler, b"\x1b[?2004h\x1b[?2004;1004;25$p");
+
+        let bracketed_paste = NamedPrivateMode::BracketedPaste.into();
+        let focus = NamedPrivateMode::ReportFocusInOut.into();
+        assert_eq!(handler.private_mode_states, vec![
+            (bracketed_paste, ModeState::Set),
+            (focus, ModeState::Reset),
+        ]);
+        assert_eq!(handler.private_mode_reports, vec![NamedPrivateMode::ShowCursor.into()]);
+
+        handler.private_mode_states.clear();
+        parser.advance(&mut handler, b"\x1b[?2004l\x1b[?2004$p");
+        assert_eq!(handler.private_mode_states, vec![(bracketed_paste, ModeState::Reset)]);
+    }
+
+    #[test]
+    fn report_tracked_sync_update() {
+        let mut parser = Processor::<TestSyncHandler>::new();
+        parser.set_track_private_modes(true);
+        let mut handler = MockHandler::default();
+
+        parser.advance(&mut handler, b"\x1b[?2026h\x1b[?2026$p\x1b[?2026l\x1b[?2026$p");
+
+        let sync_update = NamedPrivateMode::SyncUpdate.into();
+        assert_eq!(handler.private_mode_states, vec![
+            (sync_update, ModeState::Set),
+            (sync_update, ModeState::Reset),
+        ]);
+    }
+
+    #[test]
+    fn untracked_private_modes() {
+        let mut parser = Processor::<TestSyncHandler>::new();
+        let mut handler = MockHandler::default();
+
+        parser.advance(&mut handler, b"\x1b[?2004h\x1b[?2004$p");
+
+        assert!(handler.private_mode_states.is_empty());
+        assert_eq!(handler.private_mode_reports, vec![NamedPrivateMode::BracketedPaste.into()]);
+    }
+
+    #[test]
+    fn format_private_mode_report() {
+        let mode = NamedPrivateMode::BracketedPaste.into();
+        assert_eq!(format_decrpm_private(mode, ModeState::Set), "\x1b[?2004;1$y");
+        assert_eq!(
+            format_decrpm_private(PrivateMode::new(9999), ModeState::NotRecognized),
+            "\x1b[?9999;0$y"
+        );
+    }
+
     #[test]
     fn partial_sync_updates() {
         let mut parser = Processor::<TestSyncHandler>::new();
@@ -0,0 +1,171 @@
+//! Helpers for Application Program Command payloads.
+
+use alloc::vec::Vec;
+
+/// Split an APC payload into its `key=value` control data and trailing data.
+///
+/// This is the payload format used by the kitty graphics protocol, where
+/// comma-separated control keys are followed by a `;` and base64 data:
+///
+/// ```rust
+/// let (keys, data) = vte::parse_apc_kv(b"f=100,a=T;SGVsbG8=");
+///
+/// assert_eq!(keys, vec![('f', &b"100"[..]), ('a', &b"T"[..])]);
+/// assert_eq!(data, b"SGVsbG8=");
+/// ```
+///
+/// Entries which do not consist of a single ASCII character key followed by
+/// `=` are skipped. Without a `;`, the whole payload is treated as control
+/// data and the trailing data is empty.
+pub fn parse_apc_kv(payload: &[u8]) -> (Vec<(char, &[u8])>, &[u8]) {
+    let (control, data) = match payload.iter().position(|&byte| byte == b';') {
+        Some(index) => (&payload[..index], &payload[index + 1..]),
+        None => (payload, &payload[payload.len()..]),
+    };
+
+    let keys = control
+        .split(|&byte| byte == b',')
+        .filter_map(|entry| match entry {
+            [key, b'=', value @ ..] if key.is_ascii() => Some((*key as char, value)),
+            _ => None,
+        })
+        .collect();
+
+    (keys, data)
+}
+
+/// Action of a kitty graphics protocol command, selected by its `a` key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum KittyGraphicsAction {
+    /// `a=t` (the default). Transmit image data.
+    Transmit,
+    /// `a=T`. Transmit image data and display it.
+    TransmitDisplay,
+    /// `a=q`. Query support for the transmitted image.
+    Query,
+    /// `a=p`. Display a previously transmitted image.
+    Display,
+    /// `a=d`. Delete images, selected by the `d` key.
+    Delete,
+    /// `a=f`. Transmit data for an animation frame.
+    Frame,
+    /// `a=a`. Control an animation.
+    Animate,
+}
+
+/// Kitty graphics protocol command (`APC G keys ; data ST`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KittyGraphicsCommand<'a> {
+    /// Action of the command.
+    pub action: KittyGraphicsAction,
+    /// All control keys, including the `a` key.
+    ///
+    /// The keys required by each action, like `d`, `x`, `y` and `z` for
+    /// deletion, or the frame number `r` for animations, are passed through
+    /// without validation.
+    pub keys: Vec<(char, &'a [u8])>,
+    /// Base64 encoded payload.
+    pub data: &'a [u8],
+}
+
+impl<'a> KittyGraphicsCommand<'a> {
+    /// Returns the value of the last control key named `key`.
+    pub fn get(&self, key: char) -> Option<&'a [u8]> {
+        self.keys.iter().rev().find(|(k, _)| *k == key).map(|(_, value)| *value)
+    }
+}
+
+/// Parse a kitty graphics protocol APC payload.
+///
+/// The payload must start with the `G` identifying graphics commands.
+/// Commands with an unknown action return `None`.
+///
+/// ```rust
+/// use vte::{parse_kitty_graphics, KittyGraphicsAction};
+///
+/// let command = parse_kitty_graphics(b"Ga=d,d=A").unwrap();
+///
+/// assert_eq!(command.action, KittyGraphicsAction::Delete);
+/// assert_eq!(command.get('d'), Some(&b"A"[..]));
+/// ```
+pub fn parse_kitty_graphics(payload: &[u8]) -> Option<KittyGraphicsCommand<'_>> {
+    let payload = payload.strip_prefix(b"G")?;
+    let (keys, data) = parse_apc_kv(payload);
+
+    let action = keys.iter().rev().find(|(key, _)| *key == 'a').map(|(_, value)| *value);
+    let action = match action {
+        None | Some(b"t") => KittyGraphicsAction::Transmit,
+        Some(b"T") => KittyGraphicsAction::TransmitDisplay,
+        Some(b"q") => KittyGraphicsAction::Query,
+        Some(b"p") => KittyGraphicsAction::Display,
+        Some(b"d") => KittyGraphicsAction::Delete,
+        Some(b"f") => KittyGraphicsAction::Frame,
+        Some(b"a") => KittyGraphicsAction::Animate,
+        Some(_) => return None,
+    };
+
+    Some(KittyGraphicsCommand { action, keys, data })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_kitty_graphics_payload() {
+        let (keys, data) = parse_apc_kv(b"f=100,a=T,m=1;SGVsbG8=");
+
+        assert_eq!(keys, vec![('f', &b"100"[..]), ('a', &b"T"[..]), ('m', &b"1"[..])]);
+        assert_eq!(data, b"SGVsbG8=");
+    }
+
+    #[test]
+    fn parse_payload_without_data() {
+        let (keys, data) = parse_apc_kv(b"a=d,d=A");
+
+        assert_eq!(keys, vec![('a', &b"d"[..]), ('d', &b"A"[..])]);
+        assert!(data.is_empty());
+    }
+
+    #[test]
+    fn parse_kitty_delete() {
+        let command = parse_kitty_graphics(b"Ga=d,d=A").unwrap();
+
+        assert_eq!(command.action, KittyGraphicsAction::Delete);
+        assert_eq!(command.keys, vec![('a', &b"d"[..]), ('d', &b"A"[..])]);
+        assert!(command.data.is_empty());
+    }
+
+    #[test]
+    fn parse_kitty_animation() {
+        let command = parse_kitty_graphics(b"Ga=f,i=3,r=2,x=10,y=20,z=40;AAAA").unwrap();
+        assert_eq!(command.action, KittyGraphicsAction::Frame);
+        assert_eq!(command.get('r'), Some(&b"2"[..]));
+        assert_eq!(command.get('z'), Some(&b"40"[..]));
+        assert_eq!(command.data, b"AAAA");
+
+        let command = parse_kitty_graphics(b"Ga=a,i=3,s=3,v=1").unwrap();
+        assert_eq!(command.action, KittyGraphicsAction::Animate);
+        assert_eq!(command.get('s'), Some(&b"3"[..]));
+    }
+
+    #[test]
+    fn parse_kitty_actions() {
+        let action = |payload| parse_kitty_graphics(payload).map(|command| command.action);
+
+        assert_eq!(action(b"Gf=100;AAAA"), Some(KittyGraphicsAction::Transmit));
+        assert_eq!(action(b"Ga=T,f=100"), Some(KittyGraphicsAction::TransmitDisplay));
+        assert_eq!(action(b"Ga=q,i=1"), Some(KittyGraphicsAction::Query));
+        assert_eq!(action(b"Ga=p,i=1"), Some(KittyGraphicsAction::Display));
+        assert_eq!(action(b"Ga=x"), None);
+        assert_eq!(action(b"a=d"), None);
+    }
+
+    #[test]
+    fn parse_malformed_keys() {
+        let (keys, data) = parse_apc_kv(b"a,bc=1,=2,q=;;");
+
+        assert_eq!(keys, vec![('q', &b""[..])]);
+        assert_eq!(data, b";");
+    }
+}
@@ -0,0 +1,279 @@
+//! Typed representation of escape sequences for analyzing terminal output.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::time::Duration;
+
+use crate::ansi::{Attr, ClearMode, Handler, LineClearMode, Mode, PrivateMode, Processor, Timeout};
+use crate::Params;
+
+/// Escape sequence or text parsed by [`parse_all`].
+#[derive(Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Sequence {
+    /// Printed text, consecutive characters are merged.
+    Print(String),
+    /// SGR attributes, consecutive attributes are merged.
+    Sgr(Vec<Attr>),
+    /// Backspace.
+    Backspace,
+    /// Carriage return.
+    CarriageReturn,
+    /// Linefeed.
+    Linefeed,
+    /// Bell.
+    Bell,
+    /// Horizontal tabs.
+    Tab(u16),
+    /// Move the cursor up.
+    MoveUp(usize),
+    /// Move the cursor down.
+    MoveDown(usize),
+    /// Move the cursor forward.
+    MoveForward(usize),
+    /// Move the cursor backward.
+    MoveBackward(usize),
+    /// Move the cursor to a position.
+    Goto {
+        /// Zero-based line.
+        line: i32,
+        /// Zero-based column.
+        col: usize,
+    },
+    /// Move the cursor to a line.
+    GotoLine(i32),
+    /// Move the cursor to a column.
+    GotoCol(usize),
+    /// Clear the screen.
+    ClearScreen(ClearMode),
+    /// Clear the current line.
+    ClearLine(LineClearMode),
+    /// Set an ANSI mode.
+    SetMode(Mode),
+    /// Unset an ANSI mode.
+    UnsetMode(Mode),
+    /// Set a private mode.
+    SetPrivateMode(PrivateMode),
+    /// Unset a private mode.
+    UnsetPrivateMode(PrivateMode),
+    /// Set the window title.
+    SetTitle(Option<String>),
+    /// CSI sequence without built-in handling.
+    Csi {
+        /// Parameters, including their subparameters.
+        params: Vec<Vec<u16>>,
+        /// Intermediates, including private markers.
+        intermediates: Vec<u8>,
+        /// Final character.
+        action: char,
+    },
+    /// OSC sequence without built-in handling.
+    Osc {
+        /// Parameters.
+        params: Vec<Vec<u8>>,
+    },
+    /// ESC sequence without built-in handling.
+    Esc {
+        /// Intermediates.
+        intermediates: Vec<u8>,
+        /// Final byte.
+        byte: u8,
+    },
+}
+
+/// Parse all escape sequences and text in `bytes`.
+///
+/// This is intended for tools analyzing terminal output, rather than terminal
+/// emulators. Sequences which are recognized by the [`Processor`] but have no
+/// corresponding [`Sequence`] variant are omitted. Synchronized updates are
+/// not buffered.
+///
+/// ```rust
+/// use vte::ansi::{Attr, Color, NamedColor};
+/// use vte::ast::{parse_all, Sequence};
+///
+/// let sequences = parse_all(b"\x1b[31mred\x1b[0m");
+///
+/// assert_eq!(sequences, vec![
+///     Sequence::Sgr(vec![Attr::Foreground(Color::Named(NamedColor::Red))]),
+///     Sequence::Print("red".into()),
+///     Sequence::Sgr(vec![Attr::Reset]),
+/// ]);
+/// ```
+pub fn parse_all(bytes: &[u8]) -> Vec<Sequence> {
+    let mut processor = Processor::<NoTimeout>::new();
+    let mut collector = Collector::default();
+    processor.advance(&mut collector, bytes);
+    collector.0
+}
+
+/// Timeout which never delays synchronized updates.
+#[derive(Default)]
+struct NoTimeout;
+
+impl Timeout for NoTimeout {
+    fn set_timeout(&mut self, _duration: Duration) {}
+
+    fn clear_timeout(&mut self) {}
+
+    fn pending_timeout(&self) -> bool {
+        false
+    }
+}
+
+/// Handler collecting all sequences.
+#[derive(Default)]
+struct Collector(Vec<Sequence>);
+
+impl Handler for Collector {
+    fn input(&mut self, c: char) {
+        match self.0.last_mut() {
+            Some(Sequence::Print(text)) => text.push(c),
+            _ => self.0.push(Sequence::Print(c.into())),
+        }
+    }
+
+    fn terminal_attribute(&mut self, attr: Attr) {
+        match self.0.last_mut() {
+            Some(Sequence::Sgr(attrs)) => attrs.push(attr),
+            _ => self.0.push(Sequence::Sgr(alloc::vec![attr])),
+        }
+    }
+
+    fn backspace(&mut self) {
+        self.0.push(Sequence::Backspace);
+    }
+
+    fn carriage_return(&mut self) {
+        self.0.push(Sequence::CarriageReturn);
+    }
+
+    fn linefeed(&mut self) {
+        self.0.push(Sequence::Linefeed);
+    }
+
+    fn bell(&mut self) {
+        self.0.push(Sequence::Bell);
+    }
+
+    fn put_tab(&mut self, count: u16) {
+        self.0.push(Sequence::Tab(count));
+    }
+
+    fn move_up(&mut self, count: usize) {
+        self.0.push(Sequence::MoveUp(count));
+    }
+
+    fn move_down(&mut self, count: usize) {
+        self.0.push(Sequence::MoveDown(count));
+    }
+
+    fn move_forward(&mut self, count: usize) {
+        self.0.push(Sequence::MoveForward(count));
+    }
+
+    fn move_backward(&mut self, count: usize) {
+        self.0.push(Sequence::MoveBackward(count));
+    }
+
+    fn goto(&mut self, line: i32, col: usize) {
+        self.0.push(Sequence::Goto { line, col });
+    }
+
+    fn goto_line(&mut self, line: i32) {
+        self.0.push(Sequence::GotoLine(line));
+    }
+
+    fn goto_col(&mut self, col: usize) {
+        self.0.push(Sequence::GotoCol(col));
+    }
+
+    fn clear_screen(&mut self, mode: ClearMode) {
+        self.0.push(Sequence::ClearScreen(mode));
+    }
+
+    fn clear_line(&mut self, mode: LineClearMode) {
+        self.0.push(Sequence::ClearLine(mode));
+    }
+
+    fn set_mode(&mut self, mode: Mode) {
+        self.0.push(Sequence::SetMode(mode));
+    }
+
+    fn unset_mode(&mut self, mode: Mode) {
+        self.0.push(Sequence::UnsetMode(mode));
+    }
+
+    fn set_private_mode(&mut self, mode: PrivateMode) {
+        self.0.push(Sequence::SetPrivateMode(mode));
+    }
+
+    fn unset_private_mode(&mut self, mode: PrivateMode) {
+        self.0.push(Sequence::UnsetPrivateMode(mode));
+    }
+
+    fn set_title(&mut self, title: Option<String>) {
+        self.0.push(Sequence::SetTitle(title));
+    }
+
+    fn unhandled_csi(&mut self, action: char, params: &Params, intermediates: &[u8]) {
+        let params = params.iter().map(|param| param.to_vec()).collect();
+        let intermediates = intermediates.to_vec();
+        self.0.push(Sequence::Csi { params, intermediates, action });
+    }
+
+    fn unhandled_osc(&mut self, params: &[&[u8]], _bell_terminated: bool) {
+        let params = params.iter().map(|param| param.to_vec()).collect();
+        self.0.push(Sequence::Osc { params });
+    }
+
+    fn unhandled_esc(&mut self, intermediates: &[u8], byte: u8) {
+        self.0.push(Sequence::Esc { intermediates: intermediates.to_vec(), byte });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ansi::{Color, NamedColor, NamedPrivateMode};
+
+    #[test]
+    fn parse_mixed() {
+        let sequences = parse_all(b"\x1b[1;31mab\x1b[2;3H\x1b[Ac\r\n\x1b[?25l\x1b[0m\x1b[5x");
+
+        assert_eq!(sequences, vec![
+            Sequence::Sgr(vec![Attr::Bold, Attr::Foreground(Color::Named(NamedColor::Red))]),
+            Sequence::Print("ab".into()),
+            Sequence::Goto { line: 1, col: 2 },
+            Sequence::MoveUp(1),
+            Sequence::Print("c".into()),
+            Sequence::CarriageReturn,
+            Sequence::Linefeed,
+            Sequence::UnsetPrivateMode(NamedPrivateMode::ShowCursor.into()),
+            Sequence::Sgr(vec![Attr::Reset]),
+            Sequence::Csi { params: vec![vec![5]], intermediates: Vec::new(), action: 'x' },
+        ]);
+    }
+
+    #[test]
+    fn parse_sync_update() {
+        let sequences = parse_all(b"\x1b[?2026hx\x1b[?2026l");
+
+        let sync_update = NamedPrivateMode::SyncUpdate.into();
+        assert_eq!(sequences, vec![
+            Sequence::SetPrivateMode(sync_update),
+            Sequence::Print("x".into()),
+            Sequence::UnsetPrivateMode(sync_update),
+        ]);
+    }
+
+    #[test]
+    fn parse_unhandled() {
+        let sequences = parse_all(b"\x1b]9999;x\x07\x1b#3");
+
+        assert_eq!(sequences, vec![
+            Sequence::Osc { params: vec![b"9999".to_vec(), b"x".to_vec()] },
+            Sequence::Esc { intermediates: vec![b'#'], byte: b'3' },
+        ]);
+    }
+}
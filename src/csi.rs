@@ -0,0 +1,60 @@
+//! Owned Control Sequence Introducer sequences.
+
+use crate::params::Params;
+
+/// Owned copy of a CSI sequence passed to [`Perform::csi_dispatch`].
+///
+/// This allows storing sequences for later processing, for example to record
+/// or queue them. Stored sequences can be dispatched again using
+/// [`Perform::csi_dispatch_owned`].
+///
+/// [`Perform::csi_dispatch`]: crate::Perform::csi_dispatch
+/// [`Perform::csi_dispatch_owned`]: crate::Perform::csi_dispatch_owned
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
+pub struct CsiSequence {
+    /// Parameters, each followed by its subparameters.
+    pub params: Vec<Vec<u16>>,
+    /// Intermediates, including private markers.
+    pub intermediates: Vec<u8>,
+    /// Final character.
+    pub action: char,
+    /// Whether characters of the sequence were ignored.
+    pub ignore: bool,
+}
+
+impl CsiSequence {
+    /// Copy the arguments of [`Perform::csi_dispatch`].
+    ///
+    /// [`Perform::csi_dispatch`]: crate::Perform::csi_dispatch
+    pub fn new(params: &Params, intermediates: &[u8], ignore: bool, action: char) -> Self {
+        Self {
+            params: params.iter().map(|param| param.to_vec()).collect(),
+            intermediates: intermediates.to_vec(),
+            action,
+            ignore,
+        }
+    }
+
+    /// Convert the parameters back to [`Params`].
+    ///
+    /// Since [`Params`] has a fixed capacity, excess parameters are dropped.
+    /// This is indicated by the returned flag.
+    pub(crate) fn to_params(&self) -> (Params, bool) {
+        let mut params = Params::default();
+        for param in &self.params {
+            for (i, &value) in param.iter().enumerate() {
+                if params.is_full() {
+                    return (params, true);
+                }
+
+                // All but the last value of a parameter are followed by a subparameter.
+                if i + 1 == param.len() {
+                    params.push(value);
+                } else {
+                    params.extend(value);
+                }
+            }
+        }
+        (params, false)
+    }
+}
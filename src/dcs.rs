@@ -0,0 +1,37 @@
+//! Classification of Device Control Strings.
+
+/// Kind of a device control string.
+///
+/// This classifies commonly used DCS types based on their intermediates and
+/// final character, see [`Perform::dcs_hook`].
+///
+/// [`Perform::dcs_hook`]: crate::Perform::dcs_hook
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum DcsKind<'a> {
+    /// Sixel graphics (`DCS Ps ; Ps ; Ps q`).
+    Sixel,
+    /// DECRQSS - Request selection or setting (`DCS $ q`).
+    Decrqss,
+    /// XTGETTCAP - Request termcap/terminfo capabilities (`DCS + q`).
+    Xtgettcap,
+    /// Any other device control string.
+    Other {
+        /// Intermediates, including private markers.
+        intermediates: &'a [u8],
+        /// Final character.
+        action: char,
+    },
+}
+
+impl<'a> DcsKind<'a> {
+    /// Classify a DCS based on its intermediates and final character.
+    pub fn new(intermediates: &'a [u8], action: char) -> Self {
+        match (action, intermediates) {
+            ('q', []) => Self::Sixel,
+            ('q', [b'$']) => Self::Decrqss,
+            ('q', [b'+']) => Self::Xtgettcap,
+            _ => Self::Other { intermediates, action },
+        }
+    }
+}
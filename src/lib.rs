@@ -29,21 +29,50 @@
 #![deny(clippy::all, clippy::if_not_else, clippy::enum_glob_use)]
 #![cfg_attr(not(feature = "std"), no_std)]
 
-use core::mem::MaybeUninit;
+extern crate alloc;
+
 use core::str;
 
-#[cfg(not(feature = "std"))]
 use arrayvec::ArrayVec;
 
+mod apc;
+#[cfg(feature = "std")]
+mod csi;
+mod dcs;
+mod osc;
 mod params;
+#[cfg(feature = "std")]
+mod read;
+#[cfg(feature = "std")]
+mod record;
+mod state;
+pub mod sync;
 
 #[cfg(feature = "ansi")]
 pub mod ansi;
+#[cfg(feature = "ansi")]
+pub mod ast;
+pub use apc::{parse_apc_kv, parse_kitty_graphics, KittyGraphicsAction, KittyGraphicsCommand};
+#[cfg(feature = "std")]
+pub use csi::CsiSequence;
+pub use dcs::DcsKind;
+use osc::OscBoundaries;
+pub use osc::{OscParams, OscParamsExt, OscParamsIter};
 pub use params::{Params, ParamsIter};
+#[cfg(feature = "std")]
+pub use read::ReadParser;
+#[cfg(feature = "std")]
+pub use record::{Player, Recorder};
+#[cfg(feature = "trace")]
+pub use state::ParserStateKind;
+use state::ParserStateKind as State;
+pub use sync::{SyncDetector, SyncEvent};
 
 const MAX_INTERMEDIATES: usize = 2;
 const MAX_OSC_PARAMS: usize = 16;
 const MAX_OSC_RAW: usize = 1024;
+#[cfg(feature = "std")]
+const MAX_DCS_RAW: usize = 0x40_0000;
 
 /// Parser for raw _VTE_ protocol which delegates actions to a [`Perform`]
 ///
@@ -62,11 +91,19 @@ pub struct Parser<const OSC_RAW_BUF_SIZE: usize = MAX_OSC_RAW> {
     osc_raw: ArrayVec<u8, OSC_RAW_BUF_SIZE>,
     #[cfg(feature = "std")]
     osc_raw: Vec<u8>,
-    osc_params: [(usize, usize); MAX_OSC_PARAMS],
-    osc_num_params: usize,
+    osc_params: OscBoundaries,
     ignoring: bool,
     partial_utf8: [u8; 4],
     partial_utf8_len: usize,
+    max_osc_len: Option<usize>,
+    max_print_run: Option<usize>,
+    c1_introducers: bool,
+    ignore_nul: bool,
+    abort_cancelled_osc: bool,
+    #[cfg(feature = "std")]
+    dcs_raw: Option<Vec<u8>>,
+    #[cfg(feature = "std")]
+    dcs_raw_escape: bool,
 }
 
 impl Parser {
@@ -76,6 +113,97 @@ impl Parser {
     }
 }
 
+/// Builder for a [`Parser`] with non-default configuration.
+///
+/// ```rust
+/// let parser = vte::ParserBuilder::new().max_osc_len(4096).build();
+/// ```
+#[derive(Default, Debug, Clone)]
+pub struct ParserBuilder {
+    max_osc_len: Option<usize>,
+    c1_introducers: bool,
+    ignore_nul: bool,
+    abort_cancelled_osc: bool,
+}
+
+impl ParserBuilder {
+    /// Create a new builder with the default configuration.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Limit the number of bytes buffered for a single Operating System
+    /// Command.
+    ///
+    /// Bytes exceeding the limit are dropped, the OSC is still dispatched once
+    /// terminated. Without the `std` feature, the buffer is additionally
+    /// limited by the parser's `OSC_RAW_BUF_SIZE`.
+    pub fn max_osc_len(mut self, max_osc_len: usize) -> Self {
+        self.max_osc_len = Some(max_osc_len);
+        self
+    }
+
+    /// Treat the 8-bit C1 controls DCS (`0x90`), CSI (`0x9B`) and OSC (`0x9D`)
+    /// as sequence introducers.
+    ///
+    /// Since these bytes are also UTF-8 continuation bytes, they are only
+    /// handled as controls when they are not part of a multi-byte character.
+    /// Without this, all C1 controls are passed to [`Perform::execute`].
+    pub fn c1_introducers(mut self, c1_introducers: bool) -> Self {
+        self.c1_introducers = c1_introducers;
+        self
+    }
+
+    /// Drop NUL (`0x00`) in the ground state instead of passing it to
+    /// [`Perform::execute_run`].
+    ///
+    /// NUL has no effect on the screen, so this avoids dispatching padding of
+    /// NUL-padded streams. NUL within escape sequences is still executed.
+    pub fn ignore_nul(mut self, ignore_nul: bool) -> Self {
+        self.ignore_nul = ignore_nul;
+        self
+    }
+
+    /// Discard Operating System Commands cancelled by CAN (`0x18`) or SUB
+    /// (`0x1A`).
+    ///
+    /// Cancelled OSCs are reported through [`Perform::string_aborted`] instead
+    /// of being dispatched. Without this, the OSC is dispatched like it was
+    /// terminated by the cancelling byte.
+    pub fn abort_cancelled_osc(mut self, abort_cancelled_osc: bool) -> Self {
+        self.abort_cancelled_osc = abort_cancelled_osc;
+        self
+    }
+
+    /// Create a parser with the configured options.
+    pub fn build(self) -> Parser {
+        Parser {
+            max_osc_len: self.max_osc_len,
+            c1_introducers: self.c1_introducers,
+            ignore_nul: self.ignore_nul,
+            abort_cancelled_osc: self.abort_cancelled_osc,
+            ..Default::default()
+        }
+    }
+
+    /// Create a parser with the configured options and a custom size for the
+    /// Operating System Command buffer.
+    ///
+    /// ```rust
+    /// let mut p = vte::ParserBuilder::new().build_with_size::<64>();
+    /// ```
+    #[cfg(not(feature = "std"))]
+    pub fn build_with_size<const OSC_RAW_BUF_SIZE: usize>(self) -> Parser<OSC_RAW_BUF_SIZE> {
+        Parser {
+            max_osc_len: self.max_osc_len,
+            c1_introducers: self.c1_introducers,
+            ignore_nul: self.ignore_nul,
+            abort_cancelled_osc: self.abort_cancelled_osc,
+            ..Default::default()
+        }
+    }
+}
+
 impl<const OSC_RAW_BUF_SIZE: usize> Parser<OSC_RAW_BUF_SIZE> {
     /// Create a new Parser with a custom size for the Operating System Command
     /// buffer.
@@ -90,6 +218,30 @@ impl<const OSC_RAW_BUF_SIZE: usize> Parser<OSC_RAW_BUF_SIZE> {
         Default::default()
     }
 
+    /// Limit the number of bytes passed to a single [`Perform::print_str`].
+    ///
+    /// Longer runs of printable characters are split into multiple calls,
+    /// which allows bounding the work done per call. Runs are only split at
+    /// character boundaries, so a call contains at least one character. By
+    /// default runs are unbounded.
+    pub fn set_max_print_run(&mut self, max_print_run: usize) {
+        self.max_print_run = Some(max_print_run);
+    }
+
+    /// Record the raw bytes of DCS sequences.
+    ///
+    /// When enabled, every DCS is additionally passed to [`Perform::dcs_raw`]
+    /// once it has been terminated, including its introducer and terminator.
+    /// This allows re-emitting DCS sequences which are not supported, for
+    /// example when forwarding Sixel images to an outer terminal.
+    ///
+    /// DCS sequences of 4 MiB or more are not recorded.
+    #[cfg(feature = "std")]
+    pub fn set_dcs_passthrough_raw(&mut self, enabled: bool) {
+        self.dcs_raw = enabled.then(Vec::new);
+        self.dcs_raw_escape = false;
+    }
+
     #[inline]
     fn params(&self) -> &Params {
         &self.params
@@ -164,24 +316,132 @@ impl<const OSC_RAW_BUF_SIZE: usize> Parser<OSC_RAW_BUF_SIZE> {
         i
     }
 
+    /// Advance the parser state by a single, already decoded character.
+    ///
+    /// This allows feeding text which has been decoded from UTF-8 upfront,
+    /// without encoding it again. Characters are processed the same way as
+    /// their UTF-8 encoding passed to [`Self::advance`] would be. This means
+    /// non-ASCII characters are part of the raw OSC parameter bytes, while they
+    /// are dropped inside of DCS strings, since [`Perform::put`] is only called
+    /// for 7-bit bytes.
+    #[inline]
+    pub fn advance_char<P: Perform>(&mut self, performer: &mut P, c: char) {
+        if self.state != State::Ground || self.partial_utf8_len != 0 {
+            self.advance(performer, c.encode_utf8(&mut [0; 4]).as_bytes());
+            return;
+        }
+
+        match c {
+            '\x1b' => self.enter_escape(performer),
+            '\x00' if self.ignore_nul => (),
+            '\x00'..='\x1f' | '\u{80}'..='\u{9f}' => performer.execute(c as u8),
+            _ => performer.print(c),
+        }
+    }
+
+    /// Signal the end of the input stream.
+    ///
+    /// This flushes a codepoint that was cut off by the end of the input,
+    /// printing the replacement character in its place.
+    pub fn end<P: Perform>(&mut self, performer: &mut P) {
+        if self.partial_utf8_len != 0 {
+            performer.print('�');
+            self.partial_utf8_len = 0;
+        }
+    }
+
+    /// Treat a pending escape character as a standalone escape.
+    ///
+    /// When parsing interactive input, a lone `ESC` is ambiguous between the
+    /// escape key and the start of a sequence. Input readers can call this
+    /// once no further input arrived shortly after the `ESC`, which calls
+    /// [`Perform::escape`] and returns the parser to the ground state.
+    ///
+    /// Nothing happens unless the last byte was an `ESC` starting a new
+    /// sequence.
+    pub fn flush_escape<P: Perform>(&mut self, performer: &mut P) {
+        if self.state == State::Escape && self.intermediate_idx == 0 {
+            self.state = State::Ground;
+            performer.escape();
+        }
+    }
+
     #[inline(always)]
     fn change_state<P: Perform>(&mut self, performer: &mut P, byte: u8) {
+        #[cfg(any(feature = "trace", feature = "std"))]
+        let from = self.state;
+
         match self.state {
             State::CsiEntry => self.advance_csi_entry(performer, byte),
             State::CsiIgnore => self.advance_csi_ignore(performer, byte),
             State::CsiIntermediate => self.advance_csi_intermediate(performer, byte),
             State::CsiParam => self.advance_csi_param(performer, byte),
             State::DcsEntry => self.advance_dcs_entry(performer, byte),
-            State::DcsIgnore => self.anywhere(performer, byte),
+            State::DcsIgnore => self.advance_dcs_ignore(performer, byte),
             State::DcsIntermediate => self.advance_dcs_intermediate(performer, byte),
             State::DcsParam => self.advance_dcs_param(performer, byte),
             State::DcsPassthrough => self.advance_dcs_passthrough(performer, byte),
             State::Escape => self.advance_esc(performer, byte),
             State::EscapeIntermediate => self.advance_esc_intermediate(performer, byte),
             State::OscString => self.advance_osc_string(performer, byte),
-            State::SosPmApcString => self.anywhere(performer, byte),
+            State::SosPmApcString => self.advance_sos_pm_apc_string(performer, byte),
             State::Ground => unreachable!(),
         }
+
+        #[cfg(feature = "std")]
+        if from.is_dcs() || self.state.is_dcs() || self.dcs_raw_escape {
+            self.record_dcs_raw(performer, from, byte);
+        }
+
+        #[cfg(feature = "trace")]
+        if from != self.state {
+            performer.on_transition(from, self.state, byte);
+        }
+    }
+
+    /// Record a byte of a DCS for [`Perform::dcs_raw`].
+    #[cfg(feature = "std")]
+    #[inline(never)]
+    fn record_dcs_raw<P: Perform>(&mut self, performer: &mut P, from: State, byte: u8) {
+        let raw = match &mut self.dcs_raw {
+            Some(raw) => raw,
+            None => return,
+        };
+
+        if from.is_dcs() {
+            if raw.len() < MAX_DCS_RAW {
+                raw.push(byte);
+            }
+
+            // Escapes leave the DCS, wait for the next byte to check for an ST.
+            if !self.state.is_dcs() {
+                if byte == 0x1B && self.state == State::Escape {
+                    self.dcs_raw_escape = true;
+                } else {
+                    Self::dispatch_dcs_raw(performer, raw);
+                }
+            }
+        } else if self.dcs_raw_escape {
+            if byte == b'\\' && raw.len() < MAX_DCS_RAW {
+                raw.push(byte);
+            }
+            Self::dispatch_dcs_raw(performer, raw);
+            self.dcs_raw_escape = false;
+        }
+
+        if self.state == State::DcsEntry && from != State::DcsEntry {
+            raw.extend_from_slice(b"\x1bP");
+        }
+    }
+
+    /// Pass a recorded DCS to [`Perform::dcs_raw`], unless it exceeded the size
+    /// limit.
+    #[cfg(feature = "std")]
+    fn dispatch_dcs_raw<P: Perform>(performer: &mut P, raw: &mut Vec<u8>) {
+        if raw.len() < MAX_DCS_RAW {
+            performer.dcs_raw(raw);
+        }
+        raw.clear();
     }
 
     #[inline(always)]
@@ -189,23 +449,23 @@ impl<const OSC_RAW_BUF_SIZE: usize> Parser<OSC_RAW_BUF_SIZE> {
         match byte {
             0x00..=0x17 | 0x19 | 0x1C..=0x1F => performer.execute(byte),
             0x20..=0x2F => {
-                self.action_collect(byte);
+                self.action_collect(performer, byte);
                 self.state = State::CsiIntermediate
             },
             0x30..=0x39 => {
-                self.action_paramnext(byte);
+                self.action_paramnext(performer, byte);
                 self.state = State::CsiParam
             },
             0x3A => {
-                self.action_subparam();
+                self.action_subparam(performer);
                 self.state = State::CsiParam
             },
             0x3B => {
-                self.action_param();
+                self.action_param(performer);
                 self.state = State::CsiParam
             },
             0x3C..=0x3F => {
-                self.action_collect(byte);
+                self.action_collect(performer, byte);
                 self.state = State::CsiParam
             },
             0x40..=0x7E => self.action_csi_dispatch(performer, byte),
@@ -217,9 +477,11 @@ impl<const OSC_RAW_BUF_SIZE: usize> Parser<OSC_RAW_BUF_SIZE> {
     fn advance_csi_ignore<P: Perform>(&mut self, performer: &mut P, byte: u8) {
         match byte {
             0x00..=0x17 | 0x19 | 0x1C..=0x1F => performer.execute(byte),
-            0x20..=0x3F => (),
-            0x40..=0x7E => self.state = State::Ground,
-            0x7F => (),
+            0x20..=0x3F | 0x7F => performer.ignored_byte(byte),
+            0x40..=0x7E => {
+                performer.ignored_byte(byte);
+                self.state = State::Ground
+            },
             _ => self.anywhere(performer, byte),
         }
     }
@@ -228,8 +490,11 @@ impl<const OSC_RAW_BUF_SIZE: usize> Parser<OSC_RAW_BUF_SIZE> {
     fn advance_csi_intermediate<P: Perform>(&mut self, performer: &mut P, byte: u8) {
         match byte {
             0x00..=0x17 | 0x19 | 0x1C..=0x1F => performer.execute(byte),
-            0x20..=0x2F => self.action_collect(byte),
-            0x30..=0x3F => self.state = State::CsiIgnore,
+            0x20..=0x2F => self.action_collect(performer, byte),
+            0x30..=0x3F => {
+                performer.ignored_byte(byte);
+                self.state = State::CsiIgnore
+            },
             0x40..=0x7E => self.action_csi_dispatch(performer, byte),
             _ => self.anywhere(performer, byte),
         }
@@ -240,13 +505,16 @@ impl<const OSC_RAW_BUF_SIZE: usize> Parser<OSC_RAW_BUF_SIZE> {
         match byte {
             0x00..=0x17 | 0x19 | 0x1C..=0x1F => performer.execute(byte),
             0x20..=0x2F => {
-                self.action_collect(byte);
+                self.action_collect(performer, byte);
                 self.state = State::CsiIntermediate
             },
-            0x30..=0x39 => self.action_paramnext(byte),
-            0x3A => self.action_subparam(),
-            0x3B => self.action_param(),
-            0x3C..=0x3F => self.state = State::CsiIgnore,
+            0x30..=0x39 => self.action_paramnext(performer, byte),
+            0x3A => self.action_subparam(performer),
+            0x3B => self.action_param(performer),
+            0x3C..=0x3F => {
+                performer.ignored_byte(byte);
+                self.state = State::CsiIgnore
+            },
             0x40..=0x7E => self.action_csi_dispatch(performer, byte),
             0x7F => (),
             _ => self.anywhere(performer, byte),
@@ -258,23 +526,23 @@ impl<const OSC_RAW_BUF_SIZE: usize> Parser<OSC_RAW_BUF_SIZE> {
         match byte {
             0x00..=0x17 | 0x19 | 0x1C..=0x1F => (),
             0x20..=0x2F => {
-                self.action_collect(byte);
+                self.action_collect(performer, byte);
                 self.state = State::DcsIntermediate
             },
             0x30..=0x39 => {
-                self.action_paramnext(byte);
+                self.action_paramnext(performer, byte);
                 self.state = State::DcsParam
             },
             0x3A => {
-                self.action_subparam();
+                self.action_subparam(performer);
                 self.state = State::DcsParam
             },
             0x3B => {
-                self.action_param();
+                self.action_param(performer);
                 self.state = State::DcsParam
             },
             0x3C..=0x3F => {
-                self.action_collect(byte);
+                self.action_collect(performer, byte);
                 self.state = State::DcsParam
             },
             0x40..=0x7E => self.action_hook(performer, byte),
@@ -283,12 +551,23 @@ impl<const OSC_RAW_BUF_SIZE: usize> Parser<OSC_RAW_BUF_SIZE> {
         }
     }
 
+    #[inline(always)]
+    fn advance_dcs_ignore<P: Perform>(&mut self, performer: &mut P, byte: u8) {
+        match byte {
+            0x18 | 0x1A | 0x1B => self.anywhere(performer, byte),
+            _ => performer.ignored_byte(byte),
+        }
+    }
+
     #[inline(always)]
     fn advance_dcs_intermediate<P: Perform>(&mut self, performer: &mut P, byte: u8) {
         match byte {
             0x00..=0x17 | 0x19 | 0x1C..=0x1F => (),
-            0x20..=0x2F => self.action_collect(byte),
-            0x30..=0x3F => self.state = State::DcsIgnore,
+            0x20..=0x2F => self.action_collect(performer, byte),
+            0x30..=0x3F => {
+                performer.ignored_byte(byte);
+                self.state = State::DcsIgnore
+            },
             0x40..=0x7E => self.action_hook(performer, byte),
             0x7F => (),
             _ => self.anywhere(performer, byte),
@@ -300,13 +579,16 @@ impl<const OSC_RAW_BUF_SIZE: usize> Parser<OSC_RAW_BUF_SIZE> {
         match byte {
             0x00..=0x17 | 0x19 | 0x1C..=0x1F => (),
             0x20..=0x2F => {
-                self.action_collect(byte);
+                self.action_collect(performer, byte);
                 self.state = State::DcsIntermediate
             },
-            0x30..=0x39 => self.action_paramnext(byte),
-            0x3A => self.action_subparam(),
-            0x3B => self.action_param(),
-            0x3C..=0x3F => self.state = State::DcsIgnore,
+            0x30..=0x39 => self.action_paramnext(performer, byte),
+            0x3A => self.action_subparam(performer),
+            0x3B => self.action_param(performer),
+            0x3C..=0x3F => {
+                performer.ignored_byte(byte);
+                self.state = State::DcsIgnore
+            },
             0x40..=0x7E => self.action_hook(performer, byte),
             0x7F => (),
             _ => self.anywhere(performer, byte),
@@ -318,6 +600,7 @@ impl<const OSC_RAW_BUF_SIZE: usize> Parser<OSC_RAW_BUF_SIZE> {
         match byte {
             0x00..=0x17 | 0x19 | 0x1C..=0x7E => performer.put(byte),
             0x18 | 0x1A => {
+                performer.string_aborted(byte);
                 performer.unhook();
                 performer.execute(byte);
                 self.state = State::Ground
@@ -341,7 +624,7 @@ impl<const OSC_RAW_BUF_SIZE: usize> Parser<OSC_RAW_BUF_SIZE> {
         match byte {
             0x00..=0x17 | 0x19 | 0x1C..=0x1F => performer.execute(byte),
             0x20..=0x2F => {
-                self.action_collect(byte);
+                self.action_collect(performer, byte);
                 self.state = State::EscapeIntermediate
             },
             0x30..=0x4F => {
@@ -371,7 +654,7 @@ impl<const OSC_RAW_BUF_SIZE: usize> Parser<OSC_RAW_BUF_SIZE> {
             },
             0x5D => {
                 self.osc_raw.clear();
-                self.osc_num_params = 0;
+                self.osc_params.clear();
                 self.state = State::OscString
             },
             0x5E..=0x5F => self.state = State::SosPmApcString,
@@ -393,7 +676,7 @@ impl<const OSC_RAW_BUF_SIZE: usize> Parser<OSC_RAW_BUF_SIZE> {
     fn advance_esc_intermediate<P: Perform>(&mut self, performer: &mut P, byte: u8) {
         match byte {
             0x00..=0x17 | 0x19 | 0x1C..=0x1F => performer.execute(byte),
-            0x20..=0x2F => self.action_collect(byte),
+            0x20..=0x2F => self.action_collect(performer, byte),
             0x30..=0x7E => {
                 performer.esc_dispatch(self.intermediates(), self.ignoring, byte);
                 self.state = State::Ground
@@ -412,7 +695,11 @@ impl<const OSC_RAW_BUF_SIZE: usize> Parser<OSC_RAW_BUF_SIZE> {
                 self.state = State::Ground
             },
             0x18 | 0x1A => {
-                self.osc_end(performer, byte);
+                if self.abort_cancelled_osc {
+                    performer.string_aborted(byte);
+                } else {
+                    self.osc_end(performer, byte);
+                }
                 performer.execute(byte);
                 self.state = State::Ground
             },
@@ -422,11 +709,8 @@ impl<const OSC_RAW_BUF_SIZE: usize> Parser<OSC_RAW_BUF_SIZE> {
                 self.state = State::Escape
             },
             0x3B => {
-                #[cfg(not(feature = "std"))]
-                {
-                    if self.osc_raw.is_full() {
-                        return;
-                    }
+                if self.osc_raw_full() {
+                    return;
                 }
                 self.action_osc_put_param()
             },
@@ -434,6 +718,18 @@ impl<const OSC_RAW_BUF_SIZE: usize> Parser<OSC_RAW_BUF_SIZE> {
         }
     }
 
+    #[inline(always)]
+    fn advance_sos_pm_apc_string<P: Perform>(&mut self, performer: &mut P, byte: u8) {
+        match byte {
+            0x18 | 0x1A => {
+                performer.string_aborted(byte);
+                performer.execute(byte);
+                self.state = State::Ground
+            },
+            _ => self.anywhere(performer, byte),
+        }
+    }
+
     #[inline(always)]
     fn anywhere<P: Perform>(&mut self, performer: &mut P, byte: u8) {
         match byte {
@@ -473,8 +769,9 @@ impl<const OSC_RAW_BUF_SIZE: usize> Parser<OSC_RAW_BUF_SIZE> {
     }
 
     #[inline]
-    fn action_collect(&mut self, byte: u8) {
+    fn action_collect<P: Perform>(&mut self, performer: &mut P, byte: u8) {
         if self.intermediate_idx == MAX_INTERMEDIATES {
+            performer.ignored_byte(byte);
             self.ignoring = true;
         } else {
             self.intermediates[self.intermediate_idx] = byte;
@@ -484,8 +781,9 @@ impl<const OSC_RAW_BUF_SIZE: usize> Parser<OSC_RAW_BUF_SIZE> {
 
     /// Advance to the next subparameter.
     #[inline]
-    fn action_subparam(&mut self) {
+    fn action_subparam<P: Perform>(&mut self, performer: &mut P) {
         if self.params.is_full() {
+            performer.ignored_byte(b':');
             self.ignoring = true;
         } else {
             self.params.extend(self.param);
@@ -495,8 +793,9 @@ impl<const OSC_RAW_BUF_SIZE: usize> Parser<OSC_RAW_BUF_SIZE> {
 
     /// Advance to the next parameter.
     #[inline]
-    fn action_param(&mut self) {
+    fn action_param<P: Perform>(&mut self, performer: &mut P) {
         if self.params.is_full() {
+            performer.ignored_byte(b';');
             self.ignoring = true;
         } else {
             self.params.push(self.param);
@@ -506,56 +805,51 @@ impl<const OSC_RAW_BUF_SIZE: usize> Parser<OSC_RAW_BUF_SIZE> {
 
     /// Advance inside the parameter without terminating it.
     #[inline]
-    fn action_paramnext(&mut self, byte: u8) {
+    fn action_paramnext<P: Perform>(&mut self, performer: &mut P, byte: u8) {
         if self.params.is_full() {
+            performer.ignored_byte(byte);
             self.ignoring = true;
         } else {
             // Continue collecting bytes into param.
+            //
+            // All states only call this for ASCII digits, but wrapping ensures a
+            // non-digit can never cause an overflow panic.
             self.param = self.param.saturating_mul(10);
-            self.param = self.param.saturating_add((byte - b'0') as u16);
+            self.param = self.param.saturating_add(byte.wrapping_sub(b'0') as u16);
         }
     }
 
     /// Add OSC param separator.
     #[inline]
     fn action_osc_put_param(&mut self) {
-        let idx = self.osc_raw.len();
-
-        let param_idx = self.osc_num_params;
-        match param_idx {
-            // First param is special - 0 to current byte index.
-            0 => self.osc_params[param_idx] = (0, idx),
-
-            // Only process up to MAX_OSC_PARAMS.
-            MAX_OSC_PARAMS => return,
+        self.osc_params.push(self.osc_raw.len());
+    }
 
-            // All other params depend on previous indexing.
-            _ => {
-                let prev = self.osc_params[param_idx - 1];
-                let begin = prev.1;
-                self.osc_params[param_idx] = (begin, idx);
-            },
+    #[inline(always)]
+    fn action_osc_put(&mut self, byte: u8) {
+        if self.osc_raw_full() {
+            return;
         }
-
-        self.osc_num_params += 1;
+        self.osc_raw.push(byte);
     }
 
+    /// Check if the OSC buffer has no space for additional bytes.
     #[inline(always)]
-    fn action_osc_put(&mut self, byte: u8) {
+    fn osc_raw_full(&self) -> bool {
         #[cfg(not(feature = "std"))]
         {
             if self.osc_raw.is_full() {
-                return;
+                return true;
             }
         }
-        self.osc_raw.push(byte);
+        self.max_osc_len.map_or(false, |max_osc_len| self.osc_raw.len() >= max_osc_len)
     }
 
     fn osc_end<P: Perform>(&mut self, performer: &mut P, byte: u8) {
         self.action_osc_put_param();
         self.osc_dispatch(performer, byte);
         self.osc_raw.clear();
-        self.osc_num_params = 0;
+        self.osc_params.clear();
     }
 
     /// Reset escape sequence parameters and intermediates.
@@ -568,24 +862,23 @@ impl<const OSC_RAW_BUF_SIZE: usize> Parser<OSC_RAW_BUF_SIZE> {
         self.params.clear();
     }
 
+    /// Transition from the ground state to the escape state.
+    #[inline(always)]
+    fn enter_escape<P: Perform>(&mut self, _performer: &mut P) {
+        self.state = State::Escape;
+        self.reset_params();
+
+        #[cfg(feature = "trace")]
+        _performer.on_transition(State::Ground, State::Escape, 0x1B);
+    }
+
     /// Separate method for osc_dispatch that borrows self as read-only
     ///
     /// The aliasing is needed here for multiple slices into self.osc_raw
     #[inline]
     fn osc_dispatch<P: Perform>(&self, performer: &mut P, byte: u8) {
-        let mut slices: [MaybeUninit<&[u8]>; MAX_OSC_PARAMS] =
-            unsafe { MaybeUninit::uninit().assume_init() };
-
-        for (i, slice) in slices.iter_mut().enumerate().take(self.osc_num_params) {
-            let indices = self.osc_params[i];
-            *slice = MaybeUninit::new(&self.osc_raw[indices.0..indices.1]);
-        }
-
-        unsafe {
-            let num_params = self.osc_num_params;
-            let params = &slices[..num_params] as *const [MaybeUninit<&[u8]>] as *const [&[u8]];
-            performer.osc_dispatch(&*params, byte == 0x07);
-        }
+        let params = OscParams::new(&self.osc_raw, self.osc_params.as_slice());
+        performer.osc_dispatch_iter(params, byte == 0x07);
     }
 
     /// Advance the parser state from ground.
@@ -601,20 +894,18 @@ impl<const OSC_RAW_BUF_SIZE: usize> Parser<OSC_RAW_BUF_SIZE> {
 
         // If the next character is ESC, just process it and short-circuit.
         if plain_chars == 0 {
-            self.state = State::Escape;
-            self.reset_params();
+            self.enter_escape(performer);
             return 1;
         }
 
         match str::from_utf8(&bytes[..plain_chars]) {
             Ok(parsed) => {
-                Self::ground_dispatch(performer, parsed);
+                Self::ground_dispatch(performer, parsed, self.max_print_run, self.ignore_nul);
                 let mut processed = plain_chars;
 
                 // If there's another character, it must be escape so process it directly.
                 if processed < num_bytes {
-                    self.state = State::Escape;
-                    self.reset_params();
+                    self.enter_escape(performer);
                     processed += 1;
                 }
 
@@ -625,13 +916,13 @@ impl<const OSC_RAW_BUF_SIZE: usize> Parser<OSC_RAW_BUF_SIZE> {
                 // Dispatch all the valid bytes.
                 let valid_bytes = err.valid_up_to();
                 let parsed = unsafe { str::from_utf8_unchecked(&bytes[..valid_bytes]) };
-                Self::ground_dispatch(performer, parsed);
+                Self::ground_dispatch(performer, parsed, self.max_print_run, self.ignore_nul);
 
                 match err.error_len() {
                     Some(len) => {
                         // Execute C1 escapes or emit replacement character.
                         if len == 1 && bytes[valid_bytes] <= 0x9F {
-                            performer.execute(bytes[valid_bytes]);
+                            self.advance_c1(performer, bytes[valid_bytes]);
                         } else {
                             performer.print('�');
                         }
@@ -647,8 +938,7 @@ impl<const OSC_RAW_BUF_SIZE: usize> Parser<OSC_RAW_BUF_SIZE> {
                         if plain_chars < num_bytes {
                             // Process bytes cut off by escape.
                             performer.print('�');
-                            self.state = State::Escape;
-                            self.reset_params();
+                            self.enter_escape(performer);
                             plain_chars + 1
                         } else {
                             // Process bytes cut off by the buffer end.
@@ -665,6 +955,41 @@ impl<const OSC_RAW_BUF_SIZE: usize> Parser<OSC_RAW_BUF_SIZE> {
         }
     }
 
+    /// Handle a C1 control which is not part of a UTF-8 codepoint.
+    #[inline]
+    fn advance_c1<P: Perform>(&mut self, performer: &mut P, byte: u8) {
+        if !self.c1_introducers {
+            performer.execute(byte);
+            return;
+        }
+
+        let state = match byte {
+            0x90 => State::DcsEntry,
+            0x9B => State::CsiEntry,
+            0x9D => {
+                self.osc_raw.clear();
+                self.osc_params.clear();
+                State::OscString
+            },
+            _ => {
+                performer.execute(byte);
+                return;
+            },
+        };
+
+        self.reset_params();
+        self.state = state;
+
+        #[cfg(feature = "std")]
+        if let (Some(raw), State::DcsEntry) = (&mut self.dcs_raw, state) {
+            raw.clear();
+            raw.push(byte);
+        }
+
+        #[cfg(feature = "trace")]
+        performer.on_transition(State::Ground, state, byte);
+    }
+
     /// Advance the parser while processing a partial utf8 codepoint.
     #[inline]
     fn advance_partial_utf8<P: Perform>(&mut self, performer: &mut P, bytes: &[u8]) -> usize {
@@ -719,35 +1044,50 @@ impl<const OSC_RAW_BUF_SIZE: usize> Parser<OSC_RAW_BUF_SIZE> {
 
     /// Handle ground dispatch of print/execute for all characters in a string.
     #[inline]
-    fn ground_dispatch<P: Perform>(performer: &mut P, text: &str) {
-        for c in text.chars() {
+    fn ground_dispatch<P: Perform>(
+        performer: &mut P,
+        text: &str,
+        max_print_run: Option<usize>,
+        ignore_nul: bool,
+    ) {
+        let mut chars = text.chars();
+        while let Some(c) = chars.next() {
             match c {
-                '\x00'..='\x1f' | '\u{80}'..='\u{9f}' => performer.execute(c as u8),
-                _ => performer.print(c),
+                '\x00'..='\x1f' => {
+                    // Coalesce runs of the same C0 control.
+                    let rest = chars.as_str();
+                    let repeats = rest.bytes().take_while(|&byte| byte == c as u8).count();
+                    if c == '\0' && ignore_nul {
+                        chars = rest[repeats..].chars();
+                    } else if repeats == 0 {
+                        performer.execute(c as u8);
+                    } else {
+                        performer.execute_run(c as u8, repeats + 1);
+                        chars = rest[repeats..].chars();
+                    }
+                },
+                '\u{80}'..='\u{9f}' => performer.execute(c as u8),
+                _ => {
+                    // Coalesce runs of printable characters, up to the maximum run length.
+                    let rest = &text[text.len() - chars.as_str().len() - c.len_utf8()..];
+                    let mut max_len = rest.len().min(max_print_run.unwrap_or(usize::MAX));
+                    while !rest.is_char_boundary(max_len) {
+                        max_len -= 1;
+                    }
+                    let max_len = max_len.max(c.len_utf8());
+
+                    let run = &rest[..max_len];
+                    let is_control = |c| matches!(c, '\x00'..='\x1f' | '\u{80}'..='\u{9f}');
+                    let len = run.find(is_control).unwrap_or(max_len);
+
+                    performer.print_str(&rest[..len]);
+                    chars = rest[len..].chars();
+                },
             }
         }
     }
 }
 
-#[derive(PartialEq, Eq, Debug, Default, Copy, Clone)]
-enum State {
-    CsiEntry,
-    CsiIgnore,
-    CsiIntermediate,
-    CsiParam,
-    DcsEntry,
-    DcsIgnore,
-    DcsIntermediate,
-    DcsParam,
-    DcsPassthrough,
-    Escape,
-    EscapeIntermediate,
-    OscString,
-    SosPmApcString,
-    #[default]
-    Ground,
-}
-
 /// Performs actions requested by the Parser
 ///
 /// Actions in this case mean, for example, handling a CSI escape sequence
@@ -758,13 +1098,39 @@ enum State {
 /// a useful way in my own words for completeness, but the site should be
 /// referenced if something isn't clear. If the site disappears at some point in
 /// the future, consider checking archive.org.
+///
+/// All methods have default implementations, so new methods can be added
+/// without breaking existing implementations.
 pub trait Perform {
     /// Draw a character to the screen and update states.
     fn print(&mut self, _c: char) {}
 
+    /// Draw a run of printable characters.
+    ///
+    /// This is called by [`Parser::advance`] for consecutive printable
+    /// characters in the ground state, their length can be limited using
+    /// [`Parser::set_max_print_run`]. By default it calls [`Perform::print`]
+    /// for each character.
+    fn print_str(&mut self, text: &str) {
+        for c in text.chars() {
+            self.print(c);
+        }
+    }
+
     /// Execute a C0 or C1 control function.
     fn execute(&mut self, _byte: u8) {}
 
+    /// Execute a C0 control function `count` times in a row.
+    ///
+    /// This is called by [`Parser::advance`] for consecutive identical C0
+    /// controls in the ground state, instead of calling [`Perform::execute`]
+    /// for each of them.
+    fn execute_run(&mut self, byte: u8, count: usize) {
+        for _ in 0..count {
+            self.execute(byte);
+        }
+    }
+
     /// Invoked when a final character arrives in first part of device control
     /// string.
     ///
@@ -776,7 +1142,21 @@ pub trait Perform {
     ///
     /// The `ignore` flag indicates that more than two intermediates arrived and
     /// subsequent characters were ignored.
-    fn hook(&mut self, _params: &Params, _intermediates: &[u8], _ignore: bool, _action: char) {}
+    ///
+    /// The default implementation classifies the DCS and calls
+    /// [`Self::dcs_hook`], unless `ignore` is set.
+    #[inline]
+    fn hook(&mut self, params: &Params, intermediates: &[u8], ignore: bool, action: char) {
+        if !ignore {
+            self.dcs_hook(DcsKind::new(intermediates, action), params);
+        }
+    }
+
+    /// Invoked with the classified DCS kind when a device control string is
+    /// started.
+    ///
+    /// This is only called by the default implementation of [`Self::hook`].
+    fn dcs_hook(&mut self, _kind: DcsKind<'_>, _params: &Params) {}
 
     /// Pass bytes as part of a device control string to the handle chosen in
     /// `hook`. C0 controls will also be passed to the handler.
@@ -788,9 +1168,71 @@ pub trait Perform {
     /// terminated.
     fn unhook(&mut self) {}
 
+    /// Called with the raw bytes of a terminated device control string.
+    ///
+    /// This is only called when enabled through
+    /// [`Parser::set_dcs_passthrough_raw`], after [`Self::unhook`] and any
+    /// action of the byte terminating the string. The bytes include the
+    /// `ESC P` introducer and the terminator.
+    #[cfg(feature = "std")]
+    fn dcs_raw(&mut self, _bytes: &[u8]) {}
+
+    /// Called when an OSC, DCS, SOS, PM or APC string is cancelled by CAN or
+    /// SUB.
+    ///
+    /// The string's content should be discarded. An aborted DCS is still
+    /// followed by a call to [`Self::unhook`]. OSCs are only aborted when
+    /// enabled through [`ParserBuilder::abort_cancelled_osc`], otherwise they
+    /// are dispatched. Afterwards, the cancelling `byte` is passed to
+    /// [`Self::execute`].
+    fn string_aborted(&mut self, _byte: u8) {}
+
+    /// Called for every byte discarded by a sequence which is being ignored.
+    ///
+    /// This includes bytes of CSI and DCS sequences with invalid syntax, along
+    /// with parameters and intermediates exceeding the parser's limits.
+    /// Control characters which are executed while parsing the sequence are
+    /// not reported.
+    fn ignored_byte(&mut self, _byte: u8) {}
+
+    /// Called for a standalone escape character, see [`Parser::flush_escape`].
+    fn escape(&mut self) {}
+
+    /// Called whenever the parser's state changes, with the `byte` causing
+    /// the transition.
+    ///
+    /// This is intended for debugging and is only available with the `trace`
+    /// feature.
+    #[cfg(feature = "trace")]
+    fn on_transition(&mut self, _from: ParserStateKind, _to: ParserStateKind, _byte: u8) {}
+
     /// Dispatch an operating system command.
     fn osc_dispatch(&mut self, _params: &[&[u8]], _bell_terminated: bool) {}
 
+    /// Dispatch an operating system command with lazily accessed parameters.
+    ///
+    /// The parser calls this for every OSC. The default implementation collects
+    /// all parameters and forwards them to [`Self::osc_dispatch`], so only one
+    /// of the two methods should be implemented.
+    ///
+    /// Like with [`Self::osc_dispatch`], only the first 16 parameters are
+    /// available.
+    #[inline]
+    fn osc_dispatch_iter(&mut self, params: OscParams<'_>, bell_terminated: bool) {
+        // Avoid collecting the common case of OSCs with one or two parameters.
+        let mut iter = params.iter();
+        match (iter.next(), iter.next(), iter.next()) {
+            (Some(first), None, _) => self.osc_dispatch(&[first], bell_terminated),
+            (Some(first), Some(second), None) => {
+                self.osc_dispatch(&[first, second], bell_terminated)
+            },
+            _ => {
+                let params: ArrayVec<&[u8], MAX_OSC_PARAMS> = params.iter().collect();
+                self.osc_dispatch(&params, bell_terminated);
+            },
+        }
+    }
+
     /// A final character has arrived for a CSI sequence
     ///
     /// The `ignore` flag indicates that either more than two intermediates
@@ -805,6 +1247,18 @@ pub trait Perform {
     ) {
     }
 
+    /// Dispatch a stored CSI sequence.
+    ///
+    /// This is never called by the [`Parser`], it allows feeding recorded or
+    /// queued sequences back into a [`Perform`] implementation. By default it
+    /// calls [`Perform::csi_dispatch`] with the borrowed form of `sequence`.
+    #[cfg(feature = "std")]
+    fn csi_dispatch_owned(&mut self, sequence: CsiSequence) {
+        let (params, truncated) = sequence.to_params();
+        let ignore = sequence.ignore || truncated;
+        self.csi_dispatch(&params, &sequence.intermediates, ignore, sequence.action);
+    }
+
     /// The final character of an escape sequence has arrived.
     ///
     /// The `ignore` flag indicates that more than two intermediates arrived and
@@ -845,6 +1299,7 @@ mod tests {
     #[derive(Default)]
     struct Dispatcher {
         dispatched: Vec<Sequence>,
+        ignored: Vec<u8>,
     }
 
     #[derive(Debug, PartialEq, Eq)]
@@ -857,6 +1312,8 @@ mod tests {
         Print(char),
         Execute(u8),
         DcsUnhook,
+        StringAborted(u8),
+        Escape,
     }
 
     impl Perform for Dispatcher {
@@ -890,6 +1347,18 @@ mod tests {
             self.dispatched.push(Sequence::DcsUnhook);
         }
 
+        fn string_aborted(&mut self, byte: u8) {
+            self.dispatched.push(Sequence::StringAborted(byte));
+        }
+
+        fn ignored_byte(&mut self, byte: u8) {
+            self.ignored.push(byte);
+        }
+
+        fn escape(&mut self) {
+            self.dispatched.push(Sequence::Escape);
+        }
+
         fn print(&mut self, c: char) {
             self.dispatched.push(Sequence::Print(c));
         }
@@ -917,6 +1386,36 @@ mod tests {
         }
     }
 
+    #[cfg(feature = "std")]
+    #[test]
+    fn parse_osc_from_reader() {
+        let mut dispatcher = Dispatcher::default();
+        let mut parser = ReadParser::with_buffer_size(std::io::Cursor::new(OSC_BYTES), 3);
+
+        parser.run(&mut dispatcher).unwrap();
+
+        assert_eq!(dispatcher.dispatched.len(), 1);
+        match &dispatcher.dispatched[0] {
+            Sequence::Osc(params, true) => {
+                assert_eq!(params.len(), 2);
+                assert_eq!(params[0], &OSC_BYTES[2..3]);
+                assert_eq!(params[1], &OSC_BYTES[4..(OSC_BYTES.len() - 1)]);
+            },
+            _ => panic!("expected osc sequence"),
+        }
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn reader_eof_partial_utf8() {
+        let mut dispatcher = Dispatcher::default();
+        let mut parser = ReadParser::new(&b"a\xF0\x9F"[..]);
+
+        parser.run(&mut dispatcher).unwrap();
+
+        assert_eq!(dispatcher.dispatched, vec![Sequence::Print('a'), Sequence::Print('�')]);
+    }
+
     #[test]
     fn parse_empty_osc() {
         let mut dispatcher = Dispatcher::default();
@@ -931,6 +1430,32 @@ mod tests {
         }
     }
 
+    #[test]
+    fn parse_osc_empty_trailing_param() {
+        let mut dispatcher = Dispatcher::default();
+        let mut parser = Parser::new();
+
+        parser.advance(&mut dispatcher, b"\x1b]52;c;\x07\x1b]52;c\x07");
+
+        assert_eq!(dispatcher.dispatched, vec![
+            Sequence::Osc(vec![b"52".to_vec(), b"c".to_vec(), Vec::new()], true),
+            Sequence::Osc(vec![b"52".to_vec(), b"c".to_vec()], true),
+        ]);
+    }
+
+    #[test]
+    fn parse_osc_leading_empty_param() {
+        let mut dispatcher = Dispatcher::default();
+        let mut parser = Parser::new();
+
+        parser.advance(&mut dispatcher, b"\x1b];title\x07\x1b]112\x07");
+
+        assert_eq!(dispatcher.dispatched, vec![
+            Sequence::Osc(vec![Vec::new(), b"title".to_vec()], true),
+            Sequence::Osc(vec![b"112".to_vec()], true),
+        ]);
+    }
+
     #[test]
     fn parse_osc_max_params() {
         let params = ";".repeat(params::MAX_PARAMS + 1);
@@ -951,32 +1476,475 @@ mod tests {
     }
 
     #[test]
-    fn osc_bell_terminated() {
-        const INPUT: &[u8] = b"\x1b]11;ff/00/ff\x07";
+    fn parse_osc_max_params_content() {
+        let params: Vec<_> = (0..MAX_OSC_PARAMS + 2).map(|i| format!("{i}")).collect();
+        let input = format!("\x1b]{}\x07", params.join(";")).into_bytes();
         let mut dispatcher = Dispatcher::default();
         let mut parser = Parser::new();
 
-        parser.advance(&mut dispatcher, INPUT);
+        parser.advance(&mut dispatcher, &input);
 
         assert_eq!(dispatcher.dispatched.len(), 1);
         match &dispatcher.dispatched[0] {
-            Sequence::Osc(_, true) => (),
-            _ => panic!("expected osc with bell terminator"),
+            Sequence::Osc(dispatched, true) => {
+                assert_eq!(dispatched.len(), MAX_OSC_PARAMS);
+                for (dispatched, param) in dispatched.iter().zip(&params) {
+                    assert_eq!(dispatched, param.as_bytes());
+                }
+            },
+            _ => panic!("expected osc sequence"),
         }
     }
 
     #[test]
-    fn osc_c0_st_terminated() {
-        const INPUT: &[u8] = b"\x1b]11;ff/00/ff\x1b\\";
-        let mut dispatcher = Dispatcher::default();
-        let mut parser = Parser::new();
+    fn parse_osc_lazy_params() {
+        #[derive(Default)]
+        struct LazyDispatcher {
+            params: Vec<Vec<u8>>,
+            first: Option<Vec<u8>>,
+        }
 
-        parser.advance(&mut dispatcher, INPUT);
+        impl Perform for LazyDispatcher {
+            fn osc_dispatch_iter(&mut self, params: OscParams<'_>, _bell_terminated: bool) {
+                self.first = params.get(0).map(|param| param.to_vec());
+                self.params = params.iter().map(|param| param.to_vec()).collect();
+            }
+        }
 
-        assert_eq!(dispatcher.dispatched.len(), 2);
+        const INPUT: &[u8] = b"\x1b]8;id=1;https://example.com;a\x1b\\";
+
+        let mut lazy = LazyDispatcher::default();
+        Parser::new().advance(&mut lazy, INPUT);
+
+        let mut dispatcher = Dispatcher::default();
+        Parser::new().advance(&mut dispatcher, INPUT);
+
+        assert_eq!(lazy.first.as_deref(), Some(&b"8"[..]));
         match &dispatcher.dispatched[0] {
-            Sequence::Osc(_, false) => (),
-            _ => panic!("expected osc with ST terminator"),
+            Sequence::Osc(params, false) => assert_eq!(&lazy.params, params),
+            _ => panic!("expected osc sequence"),
+        }
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn csi_sequence_round_trip() {
+        #[derive(Default)]
+        struct Recorder(Vec<CsiSequence>);
+
+        impl Perform for Recorder {
+            fn csi_dispatch(&mut self, params: &Params, inter: &[u8], ignore: bool, c: char) {
+                self.0.push(CsiSequence::new(params, inter, ignore, c));
+            }
+        }
+
+        let mut recorder = Recorder::default();
+        Parser::new().advance(&mut recorder, b"\x1b[?1;4:3;38:2::1:2:3$p");
+
+        assert_eq!(recorder.0, vec![CsiSequence {
+            params: vec![vec![1], vec![4, 3], vec![38, 2, 0, 1, 2, 3]],
+            intermediates: vec![b'?', b'$'],
+            action: 'p',
+            ignore: false,
+        }]);
+
+        // Replaying a stored sequence dispatches the same sequence again.
+        let sequence = recorder.0[0].clone();
+        recorder.csi_dispatch_owned(sequence);
+        assert_eq!(recorder.0[0], recorder.0[1]);
+
+        // Sequences exceeding the parameter limit are ignored.
+        let mut dispatcher = Dispatcher::default();
+        dispatcher.csi_dispatch_owned(CsiSequence {
+            params: (0..40).map(|param| vec![param]).collect(),
+            action: 'm',
+            ..CsiSequence::default()
+        });
+        let params = (0..params::MAX_PARAMS as u16).map(|param| vec![param]).collect();
+        assert_eq!(dispatcher.dispatched, vec![Sequence::Csi(params, Vec::new(), true, 'm')]);
+    }
+
+    #[test]
+    fn execute_runs() {
+        #[derive(Default)]
+        struct RunDispatcher {
+            runs: Vec<(u8, usize)>,
+            printed: usize,
+        }
+
+        impl Perform for RunDispatcher {
+            fn print(&mut self, _c: char) {
+                self.printed += 1;
+            }
+
+            fn execute(&mut self, byte: u8) {
+                self.runs.push((byte, 1));
+            }
+
+            fn execute_run(&mut self, byte: u8, count: usize) {
+                self.runs.push((byte, count));
+            }
+        }
+
+        let mut dispatcher = RunDispatcher::default();
+        Parser::new().advance(&mut dispatcher, &[b'\r'; 1000]);
+        assert_eq!(dispatcher.runs, vec![(0x0D, 1000)]);
+
+        let mut dispatcher = RunDispatcher::default();
+        Parser::new().advance(&mut dispatcher, b"a\r\r\nb\x08\x08\x08\xc2\x85\xc2\x85\n");
+        assert_eq!(dispatcher.runs, vec![
+            (0x0D, 2),
+            (0x0A, 1),
+            (0x08, 3),
+            (0x85, 1),
+            (0x85, 1),
+            (0x0A, 1)
+        ]);
+        assert_eq!(dispatcher.printed, 2);
+
+        // The default implementation calls `execute` for every control.
+        let mut dispatcher = Dispatcher::default();
+        Parser::new().advance(&mut dispatcher, b"\r\r\r");
+        assert_eq!(dispatcher.dispatched, vec![
+            Sequence::Execute(b'\r'),
+            Sequence::Execute(b'\r'),
+            Sequence::Execute(b'\r'),
+        ]);
+    }
+
+    #[test]
+    fn osc_param_conversions() {
+        #[derive(Default)]
+        struct ConvertingDispatcher {
+            strs: Vec<Option<std::string::String>>,
+            numbers: Vec<Option<u32>>,
+        }
+
+        impl Perform for ConvertingDispatcher {
+            fn osc_dispatch_iter(&mut self, params: OscParams<'_>, _bell_terminated: bool) {
+                for i in 0..=params.len() {
+                    self.strs.push(params.str_at(i).map(|param| param.into()));
+                    self.numbers.push(params.number_at(i));
+                }
+
+                // Slices of parameters provide the same conversions.
+                let params: Vec<&[u8]> = params.iter().collect();
+                for i in 0..=params.len() {
+                    assert_eq!(params.str_at(i), self.strs[i].as_deref());
+                    assert_eq!(params.number_at(i), self.numbers[i]);
+                }
+            }
+        }
+
+        let mut dispatcher = ConvertingDispatcher::default();
+        Parser::new().advance(&mut dispatcher, OSC_BYTES);
+
+        let title = core::str::from_utf8(&OSC_BYTES[4..(OSC_BYTES.len() - 1)]).unwrap();
+        assert_eq!(dispatcher.strs, vec![Some("2".into()), Some(title.into()), None]);
+        assert_eq!(dispatcher.numbers, vec![Some(2), None, None]);
+
+        let mut dispatcher = ConvertingDispatcher::default();
+        Parser::new().advance(&mut dispatcher, b"\x1b]99999999999;;\xff\x07");
+
+        assert_eq!(dispatcher.strs, vec![Some("99999999999".into()), Some("".into()), None, None]);
+        assert_eq!(dispatcher.numbers, vec![None, None, None, None]);
+    }
+
+    #[test]
+    fn classify_dcs_hook() {
+        #[derive(Default)]
+        struct DcsClassifier {
+            kinds: Vec<(DcsKind<'static>, Vec<Vec<u16>>)>,
+        }
+
+        impl Perform for DcsClassifier {
+            fn dcs_hook(&mut self, kind: DcsKind<'_>, params: &Params) {
+                let kind = match kind {
+                    DcsKind::Sixel => DcsKind::Sixel,
+                    DcsKind::Decrqss => DcsKind::Decrqss,
+                    DcsKind::Xtgettcap => DcsKind::Xtgettcap,
+                    DcsKind::Other { .. } => DcsKind::Other { intermediates: &[], action: '?' },
+                };
+                self.kinds.push((kind, params.iter().map(|param| param.to_vec()).collect()));
+            }
+        }
+
+        let mut classifier = DcsClassifier::default();
+        let mut parser = Parser::new();
+        parser.advance(&mut classifier, b"\x1bP0;1;0q#0\x1b\\");
+        parser.advance(&mut classifier, b"\x1bP$qm\x1b\\");
+        parser.advance(&mut classifier, b"\x1bP+q544e\x1b\\");
+        parser.advance(&mut classifier, b"\x1bP1$t\x1b\\");
+
+        assert_eq!(classifier.kinds, vec![
+            (DcsKind::Sixel, vec![vec![0], vec![1], vec![0]]),
+            (DcsKind::Decrqss, vec![vec![0]]),
+            (DcsKind::Xtgettcap, vec![vec![0]]),
+            (DcsKind::Other { intermediates: &[], action: '?' }, vec![vec![1]]),
+        ]);
+    }
+
+    #[test]
+    fn classify_dcs_other() {
+        assert_eq!(DcsKind::new(b"$", 't'), DcsKind::Other { intermediates: b"$", action: 't' });
+        assert_eq!(DcsKind::new(b"$", 'q'), DcsKind::Decrqss);
+    }
+
+    /// Prefixes for entering every parser state.
+    const STATE_PREFIXES: &[&[u8]] = &[
+        b"",
+        b"\xc3",
+        b"\x1b",
+        b"\x1b(",
+        b"\x1b[",
+        b"\x1b[?",
+        b"\x1b[1;2:3",
+        b"\x1b[1 ",
+        b"\x1b[1?",
+        b"\x1bP",
+        b"\x1bP1;2",
+        b"\x1bP$",
+        b"\x1bP1?",
+        b"\x1bPq",
+        b"\x1bPq\x1b",
+        b"\x1b]",
+        b"\x1b]1;2",
+        b"\x1bX",
+        b"\x1b^",
+        b"\x1b_",
+    ];
+
+    #[test]
+    fn byte_pairs_dont_panic() {
+        struct Noop;
+        impl Perform for Noop {}
+
+        // Bytes which change the parser state or end a partial codepoint.
+        const SECOND_BYTES: &[u8] = b"\x00\x07\x18\x1b ;:09?P[\\]m\x7f\x80\x90\x9c\xc3\xff";
+
+        for prefix in STATE_PREFIXES {
+            for first in 0..=u8::MAX {
+                for &second in SECOND_BYTES {
+                    let mut parser = Parser::new();
+                    parser.advance(&mut Noop, prefix);
+                    parser.advance(&mut Noop, &[first, second]);
+                    parser.end(&mut Noop);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn cancel_osc() {
+        let mut dispatcher = Dispatcher::default();
+        let mut parser = Parser::new();
+
+        parser.advance(&mut dispatcher, b"\x1b]2;title\x18a");
+
+        assert_eq!(dispatcher.dispatched, vec![
+            Sequence::Osc(vec![b"2".to_vec(), b"title".to_vec()], false),
+            Sequence::Execute(0x18),
+            Sequence::Print('a'),
+        ]);
+    }
+
+    #[test]
+    fn abort_cancelled_osc() {
+        let mut dispatcher = Dispatcher::default();
+        let mut parser = ParserBuilder::new().abort_cancelled_osc(true).build();
+
+        parser.advance(&mut dispatcher, b"\x1b]2;title\x18a");
+
+        assert_eq!(dispatcher.dispatched, vec![
+            Sequence::StringAborted(0x18),
+            Sequence::Execute(0x18),
+            Sequence::Print('a'),
+        ]);
+    }
+
+    #[test]
+    fn cancel_dcs() {
+        let mut dispatcher = Dispatcher::default();
+        let mut parser = Parser::new();
+
+        parser.advance(&mut dispatcher, b"\x1bPqx\x1aa");
+
+        assert_eq!(dispatcher.dispatched, vec![
+            Sequence::DcsHook(vec![vec![0]], vec![], false, 'q'),
+            Sequence::DcsPut(b'x'),
+            Sequence::StringAborted(0x1A),
+            Sequence::DcsUnhook,
+            Sequence::Execute(0x1A),
+            Sequence::Print('a'),
+        ]);
+    }
+
+    #[test]
+    fn cancel_apc() {
+        let mut dispatcher = Dispatcher::default();
+        let mut parser = Parser::new();
+
+        parser.advance(&mut dispatcher, b"\x1b_Gf=100\x18a");
+
+        assert_eq!(dispatcher.dispatched, vec![
+            Sequence::StringAborted(0x18),
+            Sequence::Execute(0x18),
+            Sequence::Print('a'),
+        ]);
+    }
+
+    #[test]
+    fn ignored_csi_params() {
+        let mut input = b"\x1b[".to_vec();
+        for i in 1..=40 {
+            input.extend_from_slice(format!("{i};").as_bytes());
+        }
+        *input.last_mut().unwrap() = b'X';
+
+        let mut dispatcher = Dispatcher::default();
+        let mut parser = Parser::new();
+
+        parser.advance(&mut dispatcher, &input);
+
+        // Everything after the 32nd parameter is discarded.
+        assert_eq!(dispatcher.ignored, b"33;34;35;36;37;38;39;40");
+        assert_eq!(dispatcher.dispatched.len(), 1);
+        match &dispatcher.dispatched[0] {
+            Sequence::Csi(params, _, ignore, 'X') => {
+                assert_eq!(params.len(), params::MAX_PARAMS);
+                assert!(ignore);
+            },
+            _ => panic!("expected csi sequence"),
+        }
+    }
+
+    #[test]
+    fn ignored_csi_syntax() {
+        let mut dispatcher = Dispatcher::default();
+        let mut parser = Parser::new();
+
+        parser.advance(&mut dispatcher, b"\x1b[1<2\x07Xa");
+
+        assert_eq!(dispatcher.ignored, b"<2X");
+        assert_eq!(dispatcher.dispatched, vec![Sequence::Execute(0x07), Sequence::Print('a')]);
+    }
+
+    #[test]
+    fn ignored_dcs_syntax() {
+        let mut dispatcher = Dispatcher::default();
+        let mut parser = Parser::new();
+
+        parser.advance(&mut dispatcher, b"\x1bP1<2q data\x1b\\");
+
+        assert_eq!(dispatcher.ignored, b"<2q data");
+        assert_eq!(dispatcher.dispatched, vec![Sequence::Esc(Vec::new(), false, b'\\')]);
+    }
+
+    #[cfg(feature = "trace")]
+    #[test]
+    fn trace_transitions() {
+        #[derive(Default)]
+        struct Tracer(Vec<(ParserStateKind, ParserStateKind, u8)>);
+
+        impl Perform for Tracer {
+            fn on_transition(&mut self, from: ParserStateKind, to: ParserStateKind, byte: u8) {
+                self.0.push((from, to, byte));
+            }
+        }
+
+        let mut tracer = Tracer::default();
+        let mut parser = Parser::new();
+
+        parser.advance(&mut tracer, b"a\x1b[m");
+
+        assert_eq!(tracer.0, vec![
+            (ParserStateKind::Ground, ParserStateKind::Escape, 0x1B),
+            (ParserStateKind::Escape, ParserStateKind::CsiEntry, b'['),
+            (ParserStateKind::CsiEntry, ParserStateKind::Ground, b'm'),
+        ]);
+    }
+
+    #[test]
+    fn flush_escape() {
+        let mut dispatcher = Dispatcher::default();
+        let mut parser = Parser::new();
+
+        parser.advance(&mut dispatcher, b"\x1b");
+        parser.flush_escape(&mut dispatcher);
+        parser.flush_escape(&mut dispatcher);
+        parser.advance(&mut dispatcher, b"[A");
+
+        assert_eq!(dispatcher.dispatched, vec![
+            Sequence::Escape,
+            Sequence::Print('['),
+            Sequence::Print('A'),
+        ]);
+    }
+
+    #[test]
+    fn flush_escape_sequence() {
+        let mut dispatcher = Dispatcher::default();
+        let mut parser = Parser::new();
+
+        parser.advance(&mut dispatcher, b"\x1b(");
+        parser.flush_escape(&mut dispatcher);
+        parser.advance(&mut dispatcher, b"B\x1b[");
+        parser.flush_escape(&mut dispatcher);
+        parser.advance(&mut dispatcher, b"A");
+
+        assert_eq!(dispatcher.dispatched, vec![
+            Sequence::Esc(vec![b'('], false, b'B'),
+            Sequence::Csi(vec![vec![0]], Vec::new(), false, 'A'),
+        ]);
+    }
+
+    #[test]
+    fn osc_param_counts() {
+        let inputs: [(&[u8], &[&[u8]]); 4] = [
+            (b"\x1b]\x07", &[b""]),
+            (b"\x1b]0\x07", &[b"0"]),
+            (b"\x1b]0;a\x07", &[b"0", b"a"]),
+            (b"\x1b]0;a;b\x07", &[b"0", b"a", b"b"]),
+        ];
+
+        for (input, expected) in inputs {
+            let mut dispatcher = Dispatcher::default();
+            let mut parser = Parser::new();
+
+            parser.advance(&mut dispatcher, input);
+
+            let expected = expected.iter().map(|param| param.to_vec()).collect();
+            assert_eq!(dispatcher.dispatched, vec![Sequence::Osc(expected, true)]);
+        }
+    }
+
+    #[test]
+    fn osc_bell_terminated() {
+        const INPUT: &[u8] = b"\x1b]11;ff/00/ff\x07";
+        let mut dispatcher = Dispatcher::default();
+        let mut parser = Parser::new();
+
+        parser.advance(&mut dispatcher, INPUT);
+
+        assert_eq!(dispatcher.dispatched.len(), 1);
+        match &dispatcher.dispatched[0] {
+            Sequence::Osc(_, true) => (),
+            _ => panic!("expected osc with bell terminator"),
+        }
+    }
+
+    #[test]
+    fn osc_c0_st_terminated() {
+        const INPUT: &[u8] = b"\x1b]11;ff/00/ff\x1b\\";
+        let mut dispatcher = Dispatcher::default();
+        let mut parser = Parser::new();
+
+        parser.advance(&mut dispatcher, INPUT);
+
+        assert_eq!(dispatcher.dispatched.len(), 2);
+        match &dispatcher.dispatched[0] {
+            Sequence::Osc(_, false) => (),
+            _ => panic!("expected osc with ST terminator"),
         }
     }
 
@@ -1049,6 +2017,159 @@ mod tests {
         }
     }
 
+    #[test]
+    fn builder_max_osc_len() {
+        const INPUT: &[u8] = b"\x1b]2;abcdefgh;ijk\x07";
+
+        let mut dispatcher = Dispatcher::default();
+        let mut parser = ParserBuilder::new().max_osc_len(5).build();
+
+        parser.advance(&mut dispatcher, INPUT);
+
+        let params = vec![b"2".to_vec(), b"abcd".to_vec()];
+        assert_eq!(dispatcher.dispatched, vec![Sequence::Osc(params, true)]);
+
+        // Limit must not leak into the next sequence.
+        dispatcher.dispatched.clear();
+        parser.advance(&mut dispatcher, b"\x1b]0;a;b\x07");
+
+        let params = vec![b"0".to_vec(), b"a".to_vec(), b"b".to_vec()];
+        assert_eq!(dispatcher.dispatched, vec![Sequence::Osc(params, true)]);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn dcs_passthrough_raw() {
+        #[derive(Default)]
+        struct RawDispatcher {
+            dispatcher: Dispatcher,
+            raw: Vec<Vec<u8>>,
+        }
+
+        impl Perform for RawDispatcher {
+            fn hook(&mut self, params: &Params, intermediates: &[u8], ignore: bool, c: char) {
+                self.dispatcher.hook(params, intermediates, ignore, c);
+            }
+
+            fn put(&mut self, byte: u8) {
+                self.dispatcher.put(byte);
+            }
+
+            fn unhook(&mut self) {
+                self.dispatcher.unhook();
+            }
+
+            fn esc_dispatch(&mut self, intermediates: &[u8], ignore: bool, byte: u8) {
+                self.dispatcher.esc_dispatch(intermediates, ignore, byte);
+            }
+
+            fn dcs_raw(&mut self, bytes: &[u8]) {
+                self.raw.push(bytes.to_vec());
+            }
+        }
+
+        const SIXEL: &[u8] = b"\x1bP0;1;0q\"1;1;2;2#0;2;100;0;0#0~~$-@@\x1b\\";
+
+        let mut dispatcher = RawDispatcher::default();
+        let mut parser = Parser::new();
+
+        // Nothing is recorded until enabled.
+        parser.advance(&mut dispatcher, SIXEL);
+        assert!(dispatcher.raw.is_empty());
+
+        parser.set_dcs_passthrough_raw(true);
+        parser.advance(&mut dispatcher, b"a");
+        for chunk in SIXEL.chunks(3) {
+            parser.advance(&mut dispatcher, chunk);
+        }
+        parser.advance(&mut dispatcher, b"b");
+
+        assert_eq!(dispatcher.raw, vec![SIXEL.to_vec()]);
+
+        // Normal hook, put and unhook are still dispatched.
+        let mut expected = Dispatcher::default();
+        Parser::new().advance(&mut expected, SIXEL);
+        Parser::new().advance(&mut expected, SIXEL);
+        assert_eq!(dispatcher.dispatcher.dispatched, expected.dispatched);
+
+        // Escapes terminating the DCS without an ST are recorded without the
+        // following sequence.
+        dispatcher.raw.clear();
+        parser.advance(&mut dispatcher, b"\x1bPq#0\x1b[m\x1bP1$r\x1bP;\x18\x1bP\x1b]\x07");
+        let raw = vec![
+            b"\x1bPq#0\x1b".to_vec(),
+            b"\x1bP1$r\x1b".to_vec(),
+            b"\x1bP;\x18".to_vec(),
+            b"\x1bP\x1b".to_vec(),
+        ];
+        assert_eq!(dispatcher.raw, raw);
+
+        // DCS sequences exceeding the size limit are dropped.
+        dispatcher.raw.clear();
+        let mut dcs = b"\x1bPq".to_vec();
+        dcs.resize(MAX_DCS_RAW, b'~');
+        dcs.extend_from_slice(b"\x1b\\\x1bPq\x1b\\");
+        parser.advance(&mut dispatcher, &dcs);
+        assert_eq!(dispatcher.raw, vec![b"\x1bPq\x1b\\".to_vec()]);
+
+        // Escapes are not part of the data string, even when doubled.
+        dispatcher.raw.clear();
+        parser.advance(&mut dispatcher, b"\x1bPq\x1b\x1b\\\x1b\\");
+        assert_eq!(dispatcher.raw, vec![b"\x1bPq\x1b".to_vec()]);
+    }
+
+    #[test]
+    fn detect_sync_updates() {
+        #[derive(Default)]
+        struct SyncDispatcher {
+            sync: SyncDetector,
+            events: Vec<(SyncEvent, bool)>,
+            csi: usize,
+        }
+
+        impl Perform for SyncDispatcher {
+            fn csi_dispatch(
+                &mut self,
+                params: &Params,
+                intermediates: &[u8],
+                ignore: bool,
+                c: char,
+            ) {
+                match self.sync.csi_dispatch(params, intermediates, ignore, c) {
+                    Some(event) => self.events.push((event, self.sync.is_active())),
+                    None => self.csi += 1,
+                }
+            }
+        }
+
+        let mut dispatcher = SyncDispatcher::default();
+        let mut parser = Parser::new();
+
+        parser.advance(&mut dispatcher, &sync::BSU_CSI);
+        assert!(dispatcher.sync.is_active());
+
+        parser.advance(&mut dispatcher, b"\x1b[?2026;25h\x1b[2026h\x1b[?2026:1l\x1b[?2026$p");
+        assert!(dispatcher.sync.is_active());
+
+        parser.advance(&mut dispatcher, b"\x1b[?2026l");
+        assert!(!dispatcher.sync.is_active());
+
+        assert_eq!(dispatcher.events, vec![(SyncEvent::Begin, true), (SyncEvent::End, false)]);
+        assert_eq!(dispatcher.csi, 4);
+    }
+
+    #[test]
+    fn builder_default_matches_new() {
+        let mut dispatcher = Dispatcher::default();
+        let mut parser = ParserBuilder::new().build();
+
+        parser.advance(&mut dispatcher, OSC_BYTES);
+
+        let mut expected = Dispatcher::default();
+        Parser::new().advance(&mut expected, OSC_BYTES);
+        assert_eq!(dispatcher.dispatched, expected.dispatched);
+    }
+
     #[test]
     fn parse_csi_max_params() {
         // This will build a list of repeating '1;'s
@@ -1473,6 +2594,42 @@ mod tests {
         assert_eq!(dispatcher.dispatched[2], Sequence::Print('b'));
     }
 
+    #[test]
+    fn partial_utf8_reprocessed_once() {
+        let mut dispatcher = Dispatcher::default();
+        let mut parser = Parser::new();
+
+        // The byte after an invalid partial codepoint is parsed exactly once.
+        parser.advance(&mut dispatcher, b"\xE6\x9C");
+        parser.advance(&mut dispatcher, b"A\xE6");
+        parser.advance(&mut dispatcher, b"\x1b[m");
+
+        assert_eq!(dispatcher.dispatched, vec![
+            Sequence::Print('�'),
+            Sequence::Print('A'),
+            Sequence::Print('�'),
+            Sequence::Csi(vec![vec![0]], Vec::new(), false, 'm'),
+        ]);
+    }
+
+    #[test]
+    fn adversarial_partial_utf8() {
+        const COUNT: usize = 100_000;
+
+        let mut dispatcher = Dispatcher::default();
+        let mut parser = Parser::new();
+
+        // Every lead byte invalidates the previous partial codepoint.
+        for _ in 0..COUNT {
+            parser.advance(&mut dispatcher, b"\xE6");
+        }
+        parser.advance(&mut dispatcher, &vec![0xE6; COUNT]);
+        parser.end(&mut dispatcher);
+
+        assert_eq!(dispatcher.dispatched.len(), 2 * COUNT);
+        assert!(dispatcher.dispatched.iter().all(|sequence| *sequence == Sequence::Print('�')));
+    }
+
     #[test]
     fn partial_invalid_utf8_split() {
         const INPUT: &[u8] = b"\xE4\xBF\x99\xB5";
@@ -1487,6 +2644,60 @@ mod tests {
         assert_eq!(dispatcher.dispatched[1], Sequence::Print('�'));
     }
 
+    #[test]
+    fn advance_char_mixed_with_bytes() {
+        let mut dispatcher = Dispatcher::default();
+        let mut parser = Parser::new();
+
+        parser.advance_char(&mut dispatcher, 'a');
+        parser.advance(&mut dispatcher, b"\x1b[1m");
+        for c in "ö\n".chars() {
+            parser.advance_char(&mut dispatcher, c);
+        }
+        parser.advance_char(&mut dispatcher, '\x1b');
+        parser.advance(&mut dispatcher, b"[2m");
+
+        assert_eq!(dispatcher.dispatched, vec![
+            Sequence::Print('a'),
+            Sequence::Csi(vec![vec![1]], vec![], false, 'm'),
+            Sequence::Print('ö'),
+            Sequence::Execute(b'\n'),
+            Sequence::Csi(vec![vec![2]], vec![], false, 'm'),
+        ]);
+    }
+
+    #[test]
+    fn advance_char_multibyte_in_strings() {
+        let mut dispatcher = Dispatcher::default();
+        let mut parser = Parser::new();
+
+        parser.advance(&mut dispatcher, b"\x1b]2;");
+        for c in "日本".chars() {
+            parser.advance_char(&mut dispatcher, c);
+        }
+        parser.advance(&mut dispatcher, b"\x07\x1bPq");
+        parser.advance_char(&mut dispatcher, 'ä');
+        parser.advance(&mut dispatcher, b"\x1b\\");
+
+        assert_eq!(dispatcher.dispatched, vec![
+            Sequence::Osc(vec![b"2".to_vec(), "日本".as_bytes().to_vec()], true),
+            Sequence::DcsHook(vec![vec![0]], vec![], false, 'q'),
+            Sequence::DcsUnhook,
+            Sequence::Esc(vec![], false, b'\\'),
+        ]);
+    }
+
+    #[test]
+    fn advance_char_after_partial_utf8() {
+        let mut dispatcher = Dispatcher::default();
+        let mut parser = Parser::new();
+
+        parser.advance(&mut dispatcher, &[0xC3]);
+        parser.advance_char(&mut dispatcher, 'a');
+
+        assert_eq!(dispatcher.dispatched, vec![Sequence::Print('�'), Sequence::Print('a')]);
+    }
+
     #[test]
     fn partial_utf8_into_esc() {
         const INPUT: &[u8] = b"\xD8\x1b012";
@@ -1526,6 +2737,169 @@ mod tests {
         assert_eq!(dispatcher.dispatched[10], Sequence::Print('a'));
     }
 
+    #[test]
+    fn params_matches() {
+        #[derive(Default)]
+        struct MatchDispatcher {
+            matches: Vec<(bool, bool)>,
+        }
+
+        impl Perform for MatchDispatcher {
+            fn csi_dispatch(&mut self, params: &Params, _: &[u8], _: bool, _: char) {
+                let semicolons = params.matches(&[&[38], &[2], &[1], &[2], &[3]]);
+                let colons = params.matches(&[&[38, 2, 0, 1, 2, 3]]);
+                self.matches.push((semicolons, colons));
+            }
+        }
+
+        let mut dispatcher = MatchDispatcher::default();
+        let mut parser = Parser::new();
+        parser.advance(&mut dispatcher, b"\x1b[38;2;1;2;3m");
+        parser.advance(&mut dispatcher, b"\x1b[38:2::1:2:3m");
+        parser.advance(&mut dispatcher, b"\x1b[38;2;1;2m");
+        parser.advance(&mut dispatcher, b"\x1b[38;2;1;2;3;4m");
+        parser.advance(&mut dispatcher, b"\x1b[38:2:0:1:2m");
+
+        assert_eq!(dispatcher.matches, vec![
+            (true, false),
+            (false, true),
+            (false, false),
+            (false, false),
+            (false, false),
+        ]);
+    }
+
+    #[test]
+    fn ignore_nul() {
+        let input = b"\0\0\0a\0\x1b[\0m\0";
+
+        let mut dispatcher = Dispatcher::default();
+        Parser::new().advance(&mut dispatcher, input);
+        assert_eq!(dispatcher.dispatched, vec![
+            Sequence::Execute(0),
+            Sequence::Execute(0),
+            Sequence::Execute(0),
+            Sequence::Print('a'),
+            Sequence::Execute(0),
+            Sequence::Execute(0),
+            Sequence::Csi(vec![vec![0]], Vec::new(), false, 'm'),
+            Sequence::Execute(0),
+        ]);
+
+        let mut dispatcher = Dispatcher::default();
+        ParserBuilder::new().ignore_nul(true).build().advance(&mut dispatcher, input);
+        assert_eq!(dispatcher.dispatched, vec![
+            Sequence::Print('a'),
+            Sequence::Execute(0),
+            Sequence::Csi(vec![vec![0]], Vec::new(), false, 'm'),
+        ]);
+
+        // Decoded characters are dropped the same way.
+        let mut dispatcher = Dispatcher::default();
+        let mut parser = ParserBuilder::new().ignore_nul(true).build();
+        for c in str::from_utf8(input).unwrap().chars() {
+            parser.advance_char(&mut dispatcher, c);
+        }
+        assert_eq!(dispatcher.dispatched, vec![
+            Sequence::Print('a'),
+            Sequence::Execute(0),
+            Sequence::Csi(vec![vec![0]], Vec::new(), false, 'm'),
+        ]);
+    }
+
+    #[test]
+    fn max_print_run() {
+        use std::string::String;
+
+        #[derive(Default)]
+        struct StrDispatcher {
+            runs: Vec<String>,
+            executed: Vec<u8>,
+        }
+
+        impl Perform for StrDispatcher {
+            fn print_str(&mut self, text: &str) {
+                self.runs.push(text.into());
+            }
+
+            fn execute(&mut self, byte: u8) {
+                self.executed.push(byte);
+            }
+        }
+
+        let input = "a".repeat(10000);
+
+        let mut dispatcher = StrDispatcher::default();
+        let mut parser = Parser::new();
+        parser.advance(&mut dispatcher, input.as_bytes());
+        assert_eq!(dispatcher.runs, vec![input.clone()]);
+
+        let mut dispatcher = StrDispatcher::default();
+        let mut parser = Parser::new();
+        parser.set_max_print_run(256);
+        parser.advance(&mut dispatcher, input.as_bytes());
+        assert_eq!(dispatcher.runs.len(), 40);
+        assert!(dispatcher.runs.iter().all(|run| run.len() <= 256));
+        assert_eq!(dispatcher.runs.concat(), input);
+
+        // Runs are split at controls and character boundaries.
+        let mut dispatcher = StrDispatcher::default();
+        let mut parser = Parser::new();
+        parser.set_max_print_run(3);
+        parser.advance(&mut dispatcher, "ééé\r\u{1f600}a".as_bytes());
+        assert_eq!(dispatcher.runs, vec!["é", "é", "é", "\u{1f600}", "a"]);
+        assert_eq!(dispatcher.executed, vec![b'\r']);
+    }
+
+    #[test]
+    fn c1_introducers() {
+        const INPUT: &[u8] = b"\x9b1;31m\x9d2;title\x07\x90q#\x9c\x85";
+
+        let mut dispatcher = Dispatcher::default();
+        let mut parser = ParserBuilder::new().c1_introducers(true).build();
+
+        parser.advance(&mut dispatcher, INPUT);
+
+        assert_eq!(dispatcher.dispatched, vec![
+            Sequence::Csi(vec![vec![1], vec![31]], Vec::new(), false, 'm'),
+            Sequence::Osc(vec![b"2".to_vec(), b"title".to_vec()], true),
+            Sequence::DcsHook(vec![vec![0]], Vec::new(), false, 'q'),
+            Sequence::DcsPut(b'#'),
+            Sequence::DcsUnhook,
+            Sequence::Execute(0x85),
+        ]);
+    }
+
+    #[test]
+    fn c1_introducers_mid_utf8() {
+        // U+26DB uses `0x9B` for both of its continuation bytes.
+        const INPUT: &[u8] = "a\u{26db}b".as_bytes();
+        assert_eq!(INPUT, b"a\xe2\x9b\x9bb");
+
+        for c1_introducers in [false, true] {
+            let expected = vec![Sequence::Print('a'), Sequence::Print('⛛'), Sequence::Print('b')];
+
+            let mut dispatcher = Dispatcher::default();
+            let mut parser = ParserBuilder::new().c1_introducers(c1_introducers).build();
+            parser.advance(&mut dispatcher, INPUT);
+            assert_eq!(dispatcher.dispatched, expected);
+
+            // Continuation bytes split across multiple advances.
+            let mut dispatcher = Dispatcher::default();
+            let mut parser = ParserBuilder::new().c1_introducers(c1_introducers).build();
+            for byte in INPUT {
+                parser.advance(&mut dispatcher, &[*byte]);
+            }
+            assert_eq!(dispatcher.dispatched, expected);
+        }
+
+        // Continuation bytes after an invalid codepoint are not introducers.
+        let mut dispatcher = Dispatcher::default();
+        let mut parser = ParserBuilder::new().c1_introducers(true).build();
+        parser.advance(&mut dispatcher, b"\xe2\x9bm");
+        assert_eq!(dispatcher.dispatched, vec![Sequence::Print('�'), Sequence::Print('m')]);
+    }
+
     #[test]
     fn execute_anywhere() {
         const INPUT: &[u8] = b"\x18\x1a";
@@ -0,0 +1,232 @@
+//! Lazily accessed Operating System Command parameters.
+
+use core::fmt::{self, Debug, Formatter};
+use core::{slice, str};
+
+use crate::MAX_OSC_PARAMS;
+
+/// Parameters of an Operating System Command.
+///
+/// This is a view into the parser's OSC buffer, parameters are only sliced
+/// when accessed.
+#[derive(Copy, Clone)]
+pub struct OscParams<'a> {
+    raw: &'a [u8],
+    indices: &'a [(usize, usize)],
+}
+
+impl<'a> OscParams<'a> {
+    #[inline]
+    pub(crate) fn new(raw: &'a [u8], indices: &'a [(usize, usize)]) -> Self {
+        Self { raw, indices }
+    }
+
+    /// Returns the number of parameters.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.indices.len()
+    }
+
+    /// Returns `true` if there are no parameters present.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.indices.is_empty()
+    }
+
+    /// Returns the parameter at `index`.
+    #[inline]
+    pub fn get(&self, index: usize) -> Option<&'a [u8]> {
+        self.indices.get(index).map(|&(start, end)| &self.raw[start..end])
+    }
+
+    /// Returns an iterator over all parameters.
+    #[inline]
+    pub fn iter(&self) -> OscParamsIter<'a> {
+        OscParamsIter { raw: self.raw, indices: self.indices.iter() }
+    }
+}
+
+impl<'a> IntoIterator for OscParams<'a> {
+    type IntoIter = OscParamsIter<'a>;
+    type Item = &'a [u8];
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl Debug for OscParams<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+/// Immutable OSC parameter iterator.
+pub struct OscParamsIter<'a> {
+    raw: &'a [u8],
+    indices: slice::Iter<'a, (usize, usize)>,
+}
+
+impl<'a> Iterator for OscParamsIter<'a> {
+    type Item = &'a [u8];
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        self.indices.next().map(|&(start, end)| &self.raw[start..end])
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.indices.size_hint()
+    }
+}
+
+/// Boundaries of the parameters in the raw OSC buffer.
+///
+/// Separators are not part of the raw buffer, so each parameter starts where
+/// the previous one ended. Parameters exceeding [`MAX_OSC_PARAMS`] are dropped.
+#[derive(Default)]
+pub(crate) struct OscBoundaries {
+    indices: [(usize, usize); MAX_OSC_PARAMS],
+    len: usize,
+}
+
+impl OscBoundaries {
+    /// End the current parameter at the raw buffer index `end`.
+    #[inline]
+    pub(crate) fn push(&mut self, end: usize) {
+        if self.len == MAX_OSC_PARAMS {
+            return;
+        }
+
+        let start = self.as_slice().last().map_or(0, |&(_, prev_end)| prev_end);
+        self.indices[self.len] = (start, end);
+        self.len += 1;
+    }
+
+    /// Remove all parameters.
+    #[inline]
+    pub(crate) fn clear(&mut self) {
+        self.len = 0;
+    }
+
+    /// Returns the start and end index of all parameters.
+    #[inline]
+    pub(crate) fn as_slice(&self) -> &[(usize, usize)] {
+        &self.indices[..self.len]
+    }
+}
+
+/// Conversions for accessing individual OSC parameters.
+///
+/// This is implemented for both [`OscParams`] and the `&[&[u8]]` parameters
+/// passed to [`Perform::osc_dispatch`](crate::Perform::osc_dispatch).
+pub trait OscParamsExt {
+    /// Returns the parameter at `index`.
+    fn param_at(&self, index: usize) -> Option<&[u8]>;
+
+    /// Returns the parameter at `index`, if it is valid UTF-8.
+    #[inline]
+    fn str_at(&self, index: usize) -> Option<&str> {
+        self.param_at(index).and_then(|param| str::from_utf8(param).ok())
+    }
+
+    /// Returns the parameter at `index`, if it is a decimal number.
+    #[inline]
+    fn number_at(&self, index: usize) -> Option<u32> {
+        self.param_at(index).and_then(parse_number)
+    }
+}
+
+impl OscParamsExt for [&[u8]] {
+    #[inline]
+    fn param_at(&self, index: usize) -> Option<&[u8]> {
+        self.get(index).copied()
+    }
+}
+
+impl OscParamsExt for OscParams<'_> {
+    #[inline]
+    fn param_at(&self, index: usize) -> Option<&[u8]> {
+        self.get(index)
+    }
+}
+
+/// Parse a decimal number, rejecting empty input and overflowing values.
+pub(crate) fn parse_number(input: &[u8]) -> Option<u32> {
+    if input.is_empty() {
+        return None;
+    }
+
+    let mut num: u32 = 0;
+    for &byte in input {
+        let digit = (byte as char).to_digit(10)?;
+        num = num.checked_mul(10).and_then(|num| num.checked_add(digit))?;
+    }
+    Some(num)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::string::ToString;
+    use std::vec::Vec;
+
+    use super::*;
+
+    /// Split an OSC string the same way the parser does.
+    fn split(input: &[u8]) -> Vec<Vec<u8>> {
+        let mut raw = Vec::new();
+        let mut boundaries = OscBoundaries::default();
+        for &byte in input {
+            match byte {
+                b';' => boundaries.push(raw.len()),
+                _ => raw.push(byte),
+            }
+        }
+        boundaries.push(raw.len());
+
+        OscParams::new(&raw, boundaries.as_slice()).iter().map(<[u8]>::to_vec).collect()
+    }
+
+    #[test]
+    fn empty_osc() {
+        assert_eq!(split(b""), vec![Vec::new()]);
+    }
+
+    #[test]
+    fn single_param() {
+        assert_eq!(split(b"112"), vec![b"112".to_vec()]);
+    }
+
+    #[test]
+    fn trailing_separator() {
+        assert_eq!(split(b"52;c;"), vec![b"52".to_vec(), b"c".to_vec(), Vec::new()]);
+    }
+
+    #[test]
+    fn leading_separator() {
+        assert_eq!(split(b";title"), vec![Vec::new(), b"title".to_vec()]);
+    }
+
+    #[test]
+    fn max_params_overflow() {
+        let input: Vec<_> = (0..MAX_OSC_PARAMS + 2).map(|i| i.to_string()).collect();
+        let params = split(input.join(";").as_bytes());
+
+        assert_eq!(params.len(), MAX_OSC_PARAMS);
+        for (param, expected) in params.iter().zip(&input) {
+            assert_eq!(param, expected.as_bytes());
+        }
+    }
+
+    #[test]
+    fn clear_boundaries() {
+        let mut boundaries = OscBoundaries::default();
+        boundaries.push(3);
+        boundaries.push(5);
+        boundaries.clear();
+        boundaries.push(2);
+
+        assert_eq!(boundaries.as_slice(), &[(0, 2)]);
+    }
+}
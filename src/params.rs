@@ -44,6 +44,23 @@ impl Params {
         ParamsIter::new(self)
     }
 
+    /// Returns `true` if the parameters and their subparameters exactly match
+    /// `shape`.
+    ///
+    /// Each entry of `shape` describes one parameter, followed by its
+    /// subparameters:
+    ///
+    /// ```
+    /// # use vte::Params;
+    /// # fn is_truecolor(params: &Params) -> bool {
+    /// params.matches(&[&[38, 2, 0, 255, 0]]) || params.matches(&[&[38], &[2], &[0], &[255], &[0]])
+    /// # }
+    /// ```
+    pub fn matches(&self, shape: &[&[u16]]) -> bool {
+        let mut iter = self.iter();
+        shape.iter().all(|expected| iter.next() == Some(*expected)) && iter.next().is_none()
+    }
+
     /// Returns `true` if there is no more space for additional parameters.
     #[inline]
     pub(crate) fn is_full(&self) -> bool {
@@ -0,0 +1,89 @@
+//! Adaptor for driving a [`Parser`] from an [`io::Read`] source.
+
+use std::io::{self, Read};
+
+use crate::{Parser, Perform};
+
+/// Default number of bytes read from the source at once.
+const DEFAULT_BUFFER_SIZE: usize = 0x1000;
+
+/// Parser which pulls its input from an [`io::Read`] source.
+///
+/// This takes care of the read loop that is otherwise necessary when parsing
+/// files or other streams, including flushing the parser once the source is
+/// exhausted.
+///
+/// ```rust
+/// use std::io::Cursor;
+///
+/// use vte::{Perform, ReadParser};
+///
+/// struct Printer(String);
+///
+/// impl Perform for Printer {
+///     fn print(&mut self, c: char) {
+///         self.0.push(c);
+///     }
+/// }
+///
+/// let mut printer = Printer(String::new());
+/// let mut parser = ReadParser::new(Cursor::new(b"\x1b[1mbold"));
+/// parser.run(&mut printer).unwrap();
+///
+/// assert_eq!(printer.0, "bold");
+/// ```
+pub struct ReadParser<R: Read> {
+    reader: R,
+    parser: Parser,
+    buf: Vec<u8>,
+}
+
+impl<R: Read> ReadParser<R> {
+    /// Create a new parser reading from `reader`.
+    pub fn new(reader: R) -> Self {
+        Self::with_buffer_size(reader, DEFAULT_BUFFER_SIZE)
+    }
+
+    /// Create a new parser reading up to `buffer_size` bytes from `reader` at
+    /// once.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `buffer_size` is zero.
+    pub fn with_buffer_size(reader: R, buffer_size: usize) -> Self {
+        assert_ne!(buffer_size, 0, "read buffer size must not be zero");
+        Self { reader, parser: Parser::new(), buf: vec![0; buffer_size] }
+    }
+
+    /// Read the source until EOF, dispatching all actions to `performer`.
+    ///
+    /// Once the source is exhausted, [`Parser::end`] is called to flush any
+    /// incomplete input.
+    ///
+    /// Reads interrupted by [`io::ErrorKind::Interrupted`] are retried, all
+    /// other errors are returned to the caller. Parsing can be resumed by
+    /// calling this method again.
+    pub fn run<P: Perform>(&mut self, performer: &mut P) -> io::Result<()> {
+        loop {
+            match self.reader.read(&mut self.buf) {
+                Ok(0) => {
+                    self.parser.end(performer);
+                    return Ok(());
+                },
+                Ok(n) => self.parser.advance(performer, &self.buf[..n]),
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                Err(err) => return Err(err),
+            }
+        }
+    }
+
+    /// Get a reference to the underlying reader.
+    pub fn get_ref(&self) -> &R {
+        &self.reader
+    }
+
+    /// Consume the parser, returning the underlying reader.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
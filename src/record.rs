@@ -0,0 +1,193 @@
+//! Recording and timing-faithful replay of terminal input.
+
+use std::io::{self, Read, Write};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Header identifying recordings.
+const MAGIC: &[u8; 8] = b"VTEREC1\n";
+
+/// Recorder for the bytes read from the PTY.
+///
+/// Every call to [`Recorder::record`] stores a chunk with the time elapsed
+/// since the first chunk, allowing [`Player`] to replay the stream with its
+/// original timing.
+///
+/// The serialized format starts with the `VTEREC1\n` header, followed by the
+/// chunks. Each chunk is stored as its offset in microseconds (`u64`), the
+/// payload length (`u32`) and finally the payload itself, with all integers in
+/// little endian.
+#[derive(Debug, Default, Clone)]
+pub struct Recorder {
+    start: Option<Instant>,
+    chunks: Vec<(Duration, Vec<u8>)>,
+}
+
+impl Recorder {
+    /// Create a new, empty recorder.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record a chunk of bytes.
+    pub fn record(&mut self, bytes: &[u8]) {
+        let now = Instant::now();
+        let start = *self.start.get_or_insert(now);
+
+        // Truncate to the serialized precision, so recordings round-trip exactly.
+        let micros = u64::try_from((now - start).as_micros()).unwrap_or(u64::MAX);
+        self.chunks.push((Duration::from_micros(micros), bytes.to_vec()));
+    }
+
+    /// All recorded chunks, with their offset from the first chunk.
+    pub fn chunks(&self) -> &[(Duration, Vec<u8>)] {
+        &self.chunks
+    }
+
+    /// Serialize the recording.
+    pub fn write_to<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        writer.write_all(MAGIC)?;
+
+        for (offset, bytes) in &self.chunks {
+            let micros = offset.as_micros() as u64;
+            let len = u32::try_from(bytes.len())
+                .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "chunk too large"))?;
+
+            writer.write_all(&micros.to_le_bytes())?;
+            writer.write_all(&len.to_le_bytes())?;
+            writer.write_all(bytes)?;
+        }
+
+        writer.flush()
+    }
+}
+
+/// Player for recordings created by [`Recorder`].
+#[derive(Debug, Default, Clone)]
+pub struct Player {
+    chunks: Vec<(Duration, Vec<u8>)>,
+}
+
+impl Player {
+    /// Deserialize a recording.
+    pub fn read_from<R: Read>(mut reader: R) -> io::Result<Self> {
+        let mut magic = [0; MAGIC.len()];
+        reader.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "not a vte recording"));
+        }
+
+        let mut chunks = Vec::new();
+        let mut micros = [0; 8];
+        loop {
+            // Stop at EOF, as long as it's not in the middle of a chunk.
+            match reader.read(&mut micros[..1])? {
+                0 => break,
+                _ => reader.read_exact(&mut micros[1..])?,
+            }
+
+            let mut len = [0; 4];
+            reader.read_exact(&mut len)?;
+            // Read incrementally, to avoid allocating untrusted lengths upfront.
+            let len = u64::from(u32::from_le_bytes(len));
+            let mut bytes = Vec::new();
+            (&mut reader).take(len).read_to_end(&mut bytes)?;
+            if bytes.len() as u64 != len {
+                return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "truncated chunk"));
+            }
+
+            chunks.push((Duration::from_micros(u64::from_le_bytes(micros)), bytes));
+        }
+
+        Ok(Self { chunks })
+    }
+
+    /// All chunks of the recording, with their offset from the first chunk.
+    pub fn chunks(&self) -> &[(Duration, Vec<u8>)] {
+        &self.chunks
+    }
+
+    /// Replay the recording, passing every chunk to `f` once it is due.
+    ///
+    /// The `speed` scales the original timing, so `2.0` replays twice as fast,
+    /// while [`f64::INFINITY`] replays all chunks without any delay.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `speed` is not positive.
+    pub fn play<F: FnMut(&[u8])>(&self, speed: f64, mut f: F) {
+        assert!(speed > 0., "replay speed must be positive");
+
+        let start = Instant::now();
+        for (offset, bytes) in &self.chunks {
+            let due = offset.div_f64(speed);
+            if let Some(delay) = due.checked_sub(start.elapsed()) {
+                thread::sleep(delay);
+            }
+
+            f(bytes);
+        }
+    }
+}
+
+impl From<Recorder> for Player {
+    fn from(recorder: Recorder) -> Self {
+        Self { chunks: recorder.chunks }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Parser, Perform};
+
+    #[derive(Default)]
+    struct Printer(String);
+
+    impl Perform for Printer {
+        fn print(&mut self, c: char) {
+            self.0.push(c);
+        }
+    }
+
+    #[test]
+    fn record_round_trip() {
+        let mut recorder = Recorder::new();
+        recorder.record(b"\x1b[1mbo");
+        recorder.record(b"ld\x1b[0m");
+        recorder.record(b"");
+
+        let mut serialized = Vec::new();
+        recorder.write_to(&mut serialized).unwrap();
+        let player = Player::read_from(&serialized[..]).unwrap();
+
+        assert_eq!(player.chunks(), recorder.chunks());
+
+        let mut printer = Printer::default();
+        let mut parser = Parser::new();
+        player.play(f64::INFINITY, |bytes| parser.advance(&mut printer, bytes));
+
+        assert_eq!(printer.0, "bold");
+    }
+
+    #[test]
+    fn read_invalid_recording() {
+        let invalid_magic = Player::read_from(&b"\x1b[1mbold"[..]).unwrap_err();
+        assert_eq!(invalid_magic.kind(), io::ErrorKind::InvalidData);
+
+        let mut truncated = MAGIC.to_vec();
+        truncated.extend_from_slice(&[0; 8]);
+        truncated.extend_from_slice(&2u32.to_le_bytes());
+        truncated.push(b'a');
+        let truncated = Player::read_from(&truncated[..]).unwrap_err();
+        assert_eq!(truncated.kind(), io::ErrorKind::UnexpectedEof);
+
+        // Huge lengths fail without allocating the entire chunk.
+        let mut huge = MAGIC.to_vec();
+        huge.extend_from_slice(&[0; 8]);
+        huge.extend_from_slice(&u32::MAX.to_le_bytes());
+        huge.push(b'a');
+        let huge = Player::read_from(&huge[..]).unwrap_err();
+        assert_eq!(huge.kind(), io::ErrorKind::UnexpectedEof);
+    }
+}
@@ -0,0 +1,56 @@
+//! States of the parser's state machine.
+
+/// State of the [`Parser`].
+///
+/// The states correspond to the ones described in
+/// <https://vt100.net/emu/dec_ansi_parser>.
+///
+/// [`Parser`]: crate::Parser
+#[derive(PartialEq, Eq, Debug, Default, Copy, Clone)]
+pub enum ParserStateKind {
+    /// Start of a CSI sequence.
+    CsiEntry,
+    /// Invalid CSI sequence, ignored until its final byte.
+    CsiIgnore,
+    /// Intermediates of a CSI sequence.
+    CsiIntermediate,
+    /// Parameters of a CSI sequence.
+    CsiParam,
+    /// Start of a DCS sequence.
+    DcsEntry,
+    /// Invalid DCS sequence, ignored until it is terminated.
+    DcsIgnore,
+    /// Intermediates of a DCS sequence.
+    DcsIntermediate,
+    /// Parameters of a DCS sequence.
+    DcsParam,
+    /// Data string of a DCS sequence.
+    DcsPassthrough,
+    /// Start of an escape sequence.
+    Escape,
+    /// Intermediates of an escape sequence.
+    EscapeIntermediate,
+    /// OSC string.
+    OscString,
+    /// SOS, PM or APC string.
+    SosPmApcString,
+    /// Printable characters and control characters.
+    #[default]
+    Ground,
+}
+
+impl ParserStateKind {
+    /// Check if the state is part of a DCS sequence.
+    #[cfg(feature = "std")]
+    #[inline]
+    pub(crate) fn is_dcs(self) -> bool {
+        matches!(
+            self,
+            Self::DcsEntry
+                | Self::DcsIgnore
+                | Self::DcsIntermediate
+                | Self::DcsParam
+                | Self::DcsPassthrough
+        )
+    }
+}
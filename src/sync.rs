@@ -0,0 +1,128 @@
+//! Synchronized updates.
+//!
+//! Synchronized updates allow applications to mark the start and end of a
+//! frame, so terminals can avoid rendering partially drawn content. They are
+//! only handled automatically by the `ansi` module's `Processor`, users of
+//! [`Parser`] can detect them through [`SyncDetector`].
+//!
+//! The constants describe the sequences and limits used by the `Processor`:
+//!
+//! ```rust
+//! use vte::sync::{BSU_CSI, ESU_CSI, SYNC_ESCAPE_LEN};
+//!
+//! let output = b"\x1b[?2026hframe\x1b[?2026l";
+//!
+//! assert_eq!(output[..SYNC_ESCAPE_LEN], BSU_CSI);
+//! assert_eq!(output[output.len() - SYNC_ESCAPE_LEN..], ESU_CSI);
+//! ```
+//!
+//! [`Parser`]: crate::Parser
+
+use core::time::Duration;
+
+use crate::Params;
+
+/// Maximum time before a synchronized update is aborted.
+///
+/// The `Processor` processes all buffered bytes once no [`ESU_CSI`] arrived
+/// within this time after the last [`BSU_CSI`].
+pub const SYNC_UPDATE_TIMEOUT: Duration = Duration::from_millis(150);
+
+/// Maximum number of bytes read in one synchronized update (2MiB).
+///
+/// The `Processor` aborts synchronized updates exceeding this size.
+pub const SYNC_BUFFER_SIZE: usize = 0x20_0000;
+
+/// Number of bytes in the BSU/ESU CSI sequences.
+pub const SYNC_ESCAPE_LEN: usize = 8;
+
+/// BSU CSI sequence for beginning or extending synchronized updates.
+pub const BSU_CSI: [u8; SYNC_ESCAPE_LEN] = *b"\x1b[?2026h";
+
+/// ESU CSI sequence for terminating synchronized updates.
+pub const ESU_CSI: [u8; SYNC_ESCAPE_LEN] = *b"\x1b[?2026l";
+
+/// Start or end of a synchronized update.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum SyncEvent {
+    /// [`BSU_CSI`] began or extended a synchronized update.
+    Begin,
+    /// [`ESU_CSI`] terminated a synchronized update.
+    End,
+}
+
+/// Detector for synchronized updates in [`Perform::csi_dispatch`].
+///
+/// Like the `Processor`, only the exact [`BSU_CSI`] and [`ESU_CSI`] sequences
+/// are detected, combining mode 2026 with other private modes is ignored.
+///
+/// ```rust
+/// use vte::{Params, Parser, Perform, SyncDetector};
+///
+/// #[derive(Default)]
+/// struct Performer {
+///     sync: SyncDetector,
+/// }
+///
+/// impl Perform for Performer {
+///     fn csi_dispatch(&mut self, params: &Params, intermediates: &[u8], ignore: bool, c: char) {
+///         if self.sync.csi_dispatch(params, intermediates, ignore, c).is_some() {
+///             return;
+///         }
+///
+///         // Handle other sequences.
+///     }
+/// }
+///
+/// let mut performer = Performer::default();
+/// Parser::new().advance(&mut performer, b"\x1b[?2026h");
+/// assert!(performer.sync.is_active());
+/// ```
+///
+/// [`Perform::csi_dispatch`]: crate::Perform::csi_dispatch
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct SyncDetector {
+    active: bool,
+}
+
+impl SyncDetector {
+    /// Create a new detector outside of a synchronized update.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Check if a synchronized update is in progress.
+    pub fn is_active(&self) -> bool {
+        self.active
+    }
+
+    /// Check a CSI sequence for the start or end of a synchronized update.
+    ///
+    /// This takes the arguments of [`Perform::csi_dispatch`] and returns
+    /// [`None`] for all other sequences.
+    ///
+    /// [`Perform::csi_dispatch`]: crate::Perform::csi_dispatch
+    pub fn csi_dispatch(
+        &mut self,
+        params: &Params,
+        intermediates: &[u8],
+        ignore: bool,
+        action: char,
+    ) -> Option<SyncEvent> {
+        if ignore || intermediates != [b'?'] || params.len() != 1 {
+            return None;
+        }
+
+        match (params.iter().next(), action) {
+            (Some([2026]), 'h') => {
+                self.active = true;
+                Some(SyncEvent::Begin)
+            },
+            (Some([2026]), 'l') => {
+                self.active = false;
+                Some(SyncEvent::End)
+            },
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,118 @@
+//! Ensure parsing control sequences does not allocate.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+use vte::{Params, Parser, Perform};
+
+/// Allocator counting the allocations of the current thread.
+struct CountingAllocator;
+
+thread_local! {
+    static ALLOCATIONS: Cell<Option<usize>> = const { Cell::new(None) };
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let _ = ALLOCATIONS.try_with(|count| count.set(count.get().map(|count| count + 1)));
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+/// Count the allocations performed by `f`.
+fn count_allocations(f: impl FnOnce()) -> usize {
+    ALLOCATIONS.with(|count| count.set(Some(0)));
+    f();
+    ALLOCATIONS.with(|count| count.take()).unwrap()
+}
+
+/// SGR-heavy output, including subparameters and truecolor sequences.
+fn sgr_corpus() -> Vec<u8> {
+    let mut corpus = Vec::new();
+    for i in 0..1000u16 {
+        let line = format!(
+            "\x1b[0;1;3;4:3;38;2;{};{};{}m#\x1b[48;5;{}m \x1b[58:2::1:2:3m\x1b[m\r\n",
+            i % 256,
+            (i * 7) % 256,
+            (i * 13) % 256,
+            i % 256,
+        );
+        corpus.extend_from_slice(line.as_bytes());
+    }
+    corpus
+}
+
+#[derive(Default)]
+struct ParamsSum(u64);
+
+impl Perform for ParamsSum {
+    fn csi_dispatch(&mut self, params: &Params, _: &[u8], _: bool, _: char) {
+        for param in params {
+            self.0 += param.iter().map(|&value| u64::from(value)).sum::<u64>();
+        }
+    }
+}
+
+#[test]
+fn count_allocations_detects_allocations() {
+    let allocations = count_allocations(|| drop(Vec::<u8>::with_capacity(1)));
+    assert_eq!(allocations, 1);
+}
+
+#[test]
+fn parse_sgr_without_allocations() {
+    let corpus = sgr_corpus();
+    let mut parser = Parser::new();
+    let mut performer = ParamsSum::default();
+
+    let allocations = count_allocations(|| parser.advance(&mut performer, &corpus));
+
+    assert_eq!(allocations, 0);
+    assert_ne!(performer.0, 0);
+}
+
+#[cfg(feature = "ansi")]
+#[test]
+fn process_sgr_without_allocations() {
+    use core::time::Duration;
+
+    use vte::ansi::{Attr, Handler, Processor, Timeout};
+
+    #[derive(Default)]
+    struct NoTimeout;
+
+    impl Timeout for NoTimeout {
+        fn set_timeout(&mut self, _: Duration) {}
+
+        fn clear_timeout(&mut self) {}
+
+        fn pending_timeout(&self) -> bool {
+            false
+        }
+    }
+
+    #[derive(Default)]
+    struct AttrCount(usize);
+
+    impl Handler for AttrCount {
+        fn terminal_attribute(&mut self, _attr: Attr) {
+            self.0 += 1;
+        }
+    }
+
+    let corpus = sgr_corpus();
+    let mut processor = Processor::<NoTimeout>::new();
+    let mut handler = AttrCount::default();
+
+    let allocations = count_allocations(|| processor.advance(&mut handler, &corpus));
+
+    assert_eq!(allocations, 0);
+    assert_eq!(handler.0, 8000);
+}
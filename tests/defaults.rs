@@ -0,0 +1,30 @@
+//! Ensure traits can be implemented without overriding any methods.
+//!
+//! New trait methods must always have a default implementation, so adding them
+//! does not break downstream implementations like these.
+
+use vte::{Parser, Perform};
+
+struct EmptyPerformer;
+
+impl Perform for EmptyPerformer {}
+
+#[test]
+fn empty_perform() {
+    let mut parser = Parser::new();
+    parser.advance(&mut EmptyPerformer, b"\x1b[1mtext\x1b]2;title\x07\x1bP1$q\x1b\\");
+    parser.end(&mut EmptyPerformer);
+}
+
+#[cfg(all(feature = "ansi", feature = "std"))]
+#[test]
+fn empty_handler() {
+    use vte::ansi::{Handler, Processor, StdSyncHandler};
+
+    struct EmptyHandler;
+
+    impl Handler for EmptyHandler {}
+
+    let mut processor = Processor::<StdSyncHandler>::new();
+    processor.advance(&mut EmptyHandler, b"\x1b[1mtext\x1b]2;title\x07\x1b[?2026h");
+}
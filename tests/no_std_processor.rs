@@ -0,0 +1,61 @@
+//! Drive the ANSI processor with a custom timeout, which also works without
+//! the `std` feature.
+
+#![cfg(feature = "ansi")]
+
+use core::time::Duration;
+
+use vte::ansi::{Handler, NamedPrivateMode, PrivateMode, Processor, Timeout};
+
+/// Timeout driven by a manually advanced tick counter.
+#[derive(Default)]
+struct TickTimeout {
+    now: u64,
+    deadline: Option<u64>,
+}
+
+impl Timeout for TickTimeout {
+    fn set_timeout(&mut self, duration: Duration) {
+        self.deadline = Some(self.now + duration.as_millis() as u64);
+    }
+
+    fn clear_timeout(&mut self) {
+        self.deadline = None;
+    }
+
+    fn pending_timeout(&self) -> bool {
+        self.deadline.map_or(false, |deadline| deadline > self.now)
+    }
+}
+
+#[derive(Default)]
+struct TextHandler {
+    text: Vec<char>,
+    sync_ended: bool,
+}
+
+impl Handler for TextHandler {
+    fn input(&mut self, c: char) {
+        self.text.push(c);
+    }
+
+    fn unset_private_mode(&mut self, mode: PrivateMode) {
+        if mode == PrivateMode::Named(NamedPrivateMode::SyncUpdate) {
+            self.sync_ended = true;
+        }
+    }
+}
+
+#[test]
+fn processor_with_custom_timeout() {
+    let mut processor = Processor::<TickTimeout>::new();
+    let mut handler = TextHandler::default();
+
+    processor.advance(&mut handler, b"\x1b[?2026hab");
+    assert!(processor.sync_timeout().pending_timeout());
+    assert!(handler.text.is_empty());
+
+    processor.advance(&mut handler, b"\x1b[?2026l");
+    assert!(handler.sync_ended);
+    assert_eq!(handler.text, ['a', 'b']);
+}